@@ -10,79 +10,556 @@ make the user wait more than necessary.
 We’ll simulate calling this hypothetical algorithm with the function `simulated_expensive_calculation`
 */
 
+pub mod history;
+pub mod rng;
+pub mod schedule;
+pub mod workout;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use self::rng::Rng;
+use self::workout::{UserProfile, WorkoutPlan};
+
+// Where the cacher gets the current time from. Time-based eviction asks the clock instead of
+// `Instant::now()` directly so tests can move time forward by hand instead of sleeping.
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+// Real clock: time elapsed since the clock was created
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// How the cacher keeps its memory bounded
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EvictionPolicy {
+    // Keep every value forever
+    Unbounded,
+    // Keep at most `capacity` values, dropping the least recently used one first
+    Lru { capacity: usize },
+    // Drop values once they are older than `ttl`
+    Ttl { ttl: Duration },
+}
 
-struct Cacher<T>
+struct Entry<V> {
+    value: V,
+    // Key of the entry in `Cacher::order`
+    tick: u64,
+    inserted_at: Duration,
+}
+
+// The cacher memoizes every argument it has seen, not only the most recent one, so asking
+// for 4, then 10, then 4 again runs the expensive closure twice instead of three times.
+pub struct Cacher<T, K, V>
 where
-    T: Fn(u32) -> u32,
+    T: Fn(K) -> V,
 {
     calculation: T,
-    value: Option<u32>,
+    values: HashMap<K, Entry<V>>,
+    // Keys from the next one to evict to the last one, by last use for `Lru` and by insertion
+    // for `Ttl`, so eviction does not have to look at every entry. Unused for `Unbounded`.
+    order: BTreeMap<u64, K>,
+    policy: EvictionPolicy,
+    clock: Box<dyn Clock>,
+    // Increases on every lookup
+    tick: u64,
+    hits: u64,
+    misses: u64,
 }
 
-impl<T> Cacher<T>
+impl<T, K, V> Cacher<T, K, V>
 where
-    T: Fn(u32) -> u32,
+    T: Fn(K) -> V,
+    K: Eq + Hash + Clone,
+    V: Clone,
 {
-    fn new(calculation: T) -> Cacher<T> {
+    pub fn new(calculation: T) -> Cacher<T, K, V> {
+        Cacher::with_policy(calculation, EvictionPolicy::Unbounded)
+    }
+
+    pub fn with_policy(calculation: T, policy: EvictionPolicy) -> Cacher<T, K, V> {
+        Cacher::with_policy_and_clock(calculation, policy, Box::new(SystemClock::new()))
+    }
+
+    pub fn with_policy_and_clock(
+        calculation: T,
+        policy: EvictionPolicy,
+        clock: Box<dyn Clock>,
+    ) -> Cacher<T, K, V> {
         Cacher {
             calculation,
-            value: None,
+            values: HashMap::new(),
+            order: BTreeMap::new(),
+            policy,
+            clock,
+            tick: 0,
+            hits: 0,
+            misses: 0,
         }
     }
 
-    fn value(&mut self, arg: u32) -> u32 {
-        match self.value {
-            Some(v) => {
-                // If value is changed then save new otherwise return already cached value
-                if v != arg {
-                    let new_value = (self.calculation)(arg);
-                    self.value = Some(new_value);
-                    new_value
-                } else {
-                    v
+    pub fn value(&mut self, arg: K) -> V {
+        let now = self.clock.now();
+        self.tick += 1;
+
+        if let Some(entry) = self.values.get_mut(&arg) {
+            let expired = match self.policy {
+                // A clock that went backwards counts as no time passing
+                EvictionPolicy::Ttl { ttl } => now.saturating_sub(entry.inserted_at) >= ttl,
+                _ => false,
+            };
+
+            if !expired {
+                if let EvictionPolicy::Lru { .. } = self.policy {
+                    self.order.remove(&entry.tick);
+                    entry.tick = self.tick;
+                    self.order.insert(self.tick, arg);
                 }
+                self.hits += 1;
+                return entry.value.clone();
+            }
+
+            self.order.remove(&entry.tick);
+            self.values.remove(&arg);
+        }
+
+        self.misses += 1;
+        let v = (self.calculation)(arg.clone());
+        self.insert(arg, v.clone(), now);
+        v
+    }
+
+    fn insert(&mut self, arg: K, value: V, now: Duration) {
+        match self.policy {
+            EvictionPolicy::Unbounded => {}
+            EvictionPolicy::Lru { capacity } => {
+                if capacity == 0 {
+                    return;
+                }
+                while self.values.len() >= capacity {
+                    self.evict_first();
+                }
+            }
+            EvictionPolicy::Ttl { ttl } => self.purge_expired(now, ttl),
+        }
+
+        if self.policy != EvictionPolicy::Unbounded {
+            self.order.insert(self.tick, arg.clone());
+        }
+        self.values.insert(
+            arg,
+            Entry {
+                value,
+                tick: self.tick,
+                inserted_at: now,
+            },
+        );
+    }
+
+    // Drops the least recently used value for `Lru`, the oldest one for `Ttl`
+    fn evict_first(&mut self) {
+        if let Some((_, key)) = self.order.pop_first() {
+            self.values.remove(&key);
+        }
+    }
+
+    // Drops expired values from the oldest one on and stops at the first one still valid, so
+    // an insert only pays for the values it removes. After the clock went backwards a newer
+    // value can expire first, it is then dropped when it is looked up or reaches the front.
+    fn purge_expired(&mut self, now: Duration, ttl: Duration) {
+        while let Some((_, key)) = self.order.first_key_value() {
+            let expired = self
+                .values
+                .get(key)
+                .is_none_or(|entry| now.saturating_sub(entry.inserted_at) >= ttl);
+            if !expired {
+                break;
             }
-            None => {
+            self.evict_first();
+        }
+    }
+
+    // Number of values currently held
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    // Number of calls answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    // Number of calls that had to run the calculation
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+}
+
+// A cacher that can be shared between threads. `value` takes `&self`, and when several threads
+// ask for the same key at once only the first one runs the calculation; the others block
+// until its result is ready instead of repeating the slow work ("single-flight").
+pub struct SharedCacher<T, K, V>
+where
+    T: Fn(K) -> V,
+{
+    calculation: T,
+    slots: Mutex<HashMap<K, Arc<Slot<V>>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+enum SlotState<V> {
+    Pending,
+    Ready(V),
+    // The thread computing the value panicked, waiters have to try again
+    Abandoned,
+}
+
+struct Slot<V> {
+    state: Mutex<SlotState<V>>,
+    ready: Condvar,
+}
+
+impl<V> Slot<V> {
+    fn pending() -> Slot<V> {
+        Slot {
+            state: Mutex::new(SlotState::Pending),
+            ready: Condvar::new(),
+        }
+    }
+
+    fn finish(&self, state: SlotState<V>) {
+        *self.state.lock().unwrap() = state;
+        self.ready.notify_all();
+    }
+}
+
+// Makes sure waiters are woken up and the pending slot is removed if the calculation panics
+struct InFlight<'a, K, V>
+where
+    K: Eq + Hash,
+{
+    key: Option<K>,
+    slot: &'a Arc<Slot<V>>,
+    slots: &'a Mutex<HashMap<K, Arc<Slot<V>>>>,
+}
+
+impl<'a, K, V> Drop for InFlight<'a, K, V>
+where
+    K: Eq + Hash,
+{
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            if let Ok(mut slots) = self.slots.lock() {
+                slots.remove(&key);
+            }
+            if let Ok(mut state) = self.slot.state.lock() {
+                *state = SlotState::Abandoned;
+            }
+            self.slot.ready.notify_all();
+        }
+    }
+}
+
+impl<T, K, V> SharedCacher<T, K, V>
+where
+    T: Fn(K) -> V,
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new(calculation: T) -> SharedCacher<T, K, V> {
+        SharedCacher {
+            calculation,
+            slots: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn value(&self, arg: K) -> V {
+        loop {
+            let (slot, is_leader) = {
+                let mut slots = self.slots.lock().unwrap();
+                match slots.get(&arg) {
+                    Some(slot) => (Arc::clone(slot), false),
+                    None => {
+                        let slot = Arc::new(Slot::pending());
+                        slots.insert(arg.clone(), Arc::clone(&slot));
+                        (slot, true)
+                    }
+                }
+            };
+
+            if is_leader {
+                self.misses.fetch_add(1, Ordering::SeqCst);
+                let mut in_flight = InFlight {
+                    key: Some(arg.clone()),
+                    slot: &slot,
+                    slots: &self.slots,
+                };
                 let v = (self.calculation)(arg);
-                self.value = Some(v);
-                v
+                in_flight.key = None;
+                slot.finish(SlotState::Ready(v.clone()));
+                return v;
+            }
+
+            let mut state = slot.state.lock().unwrap();
+            loop {
+                match &*state {
+                    SlotState::Pending => state = slot.ready.wait(state).unwrap(),
+                    SlotState::Ready(v) => {
+                        self.hits.fetch_add(1, Ordering::SeqCst);
+                        return v.clone();
+                    }
+                    SlotState::Abandoned => break,
+                }
             }
         }
     }
+
+    // Number of calls answered from the cache or from another thread's calculation
+    #[allow(dead_code)]
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::SeqCst)
+    }
+
+    // Number of calls that had to run the calculation
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::SeqCst)
+    }
 }
 
-pub fn generate_workout(intensity: u32, random_number: u32) {
-    let mut expensive_result = Cacher::new(|num| {
-        println!("calculating slowly...");
-        thread::sleep(Duration::from_secs(2));
-        num
-    });
+// What a fallible cacher does with errors returned by its calculation
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NegativeCachePolicy {
+    // Errors are not remembered, the next call runs the calculation again
+    DoNotCache,
+    // Errors are remembered for `ttl` so a failing calculation is not retried on every call
+    CacheFor { ttl: Duration },
+}
 
-    if intensity < 25 {
-        println!("Today, do {} pushups!", expensive_result.value(intensity));
-        println!("Next, do {} situps!", expensive_result.value(intensity));
-        println!(
-            "value with arg {} is {}",
-            intensity,
-            expensive_result.value(intensity)
-        );
-        println!("value with arg 4 is {}", expensive_result.value(4));
-        println!("value with arg 4 is {}", expensive_result.value(4));
-        println!("value with arg 4 is {}", expensive_result.value(4));
-    } else {
-        if random_number == 3 {
-            println!("Take a break today! Remember to stay hydrated!");
-        } else {
-            println!(
-                "Today, run for {} minutes!",
-                expensive_result.value(intensity)
-            );
+// Like `Cacher`, but for calculations that can fail. Successful values are memoized per
+// argument; errors are handed back to the caller and only kept if the policy says so.
+pub struct FallibleCacher<T, K, V, E>
+where
+    T: Fn(K) -> Result<V, E>,
+{
+    calculation: T,
+    values: HashMap<K, V>,
+    errors: HashMap<K, (E, Duration)>,
+    negative_cache: NegativeCachePolicy,
+    clock: Box<dyn Clock>,
+    hits: u64,
+    misses: u64,
+}
+
+impl<T, K, V, E> FallibleCacher<T, K, V, E>
+where
+    T: Fn(K) -> Result<V, E>,
+    K: Eq + Hash + Clone,
+    V: Clone,
+    E: Clone,
+{
+    pub fn new(calculation: T) -> FallibleCacher<T, K, V, E> {
+        FallibleCacher::with_negative_cache(calculation, NegativeCachePolicy::DoNotCache)
+    }
+
+    pub fn with_negative_cache(
+        calculation: T,
+        negative_cache: NegativeCachePolicy,
+    ) -> FallibleCacher<T, K, V, E> {
+        FallibleCacher::with_negative_cache_and_clock(
+            calculation,
+            negative_cache,
+            Box::new(SystemClock::new()),
+        )
+    }
+
+    pub fn with_negative_cache_and_clock(
+        calculation: T,
+        negative_cache: NegativeCachePolicy,
+        clock: Box<dyn Clock>,
+    ) -> FallibleCacher<T, K, V, E> {
+        FallibleCacher {
+            calculation,
+            values: HashMap::new(),
+            errors: HashMap::new(),
+            negative_cache,
+            clock,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn value(&mut self, arg: K) -> Result<V, E> {
+        if let Some(v) = self.values.get(&arg) {
+            self.hits += 1;
+            return Ok(v.clone());
+        }
+
+        let now = self.clock.now();
+        if let NegativeCachePolicy::CacheFor { ttl } = self.negative_cache {
+            if let Some((e, failed_at)) = self.errors.get(&arg) {
+                // A clock that went backwards counts as no time passing
+                if now.saturating_sub(*failed_at) < ttl {
+                    self.hits += 1;
+                    return Err(e.clone());
+                }
+                self.errors.remove(&arg);
+            }
         }
+
+        self.misses += 1;
+        match (self.calculation)(arg.clone()) {
+            Ok(v) => {
+                self.values.insert(arg, v.clone());
+                Ok(v)
+            }
+            Err(e) => {
+                if let NegativeCachePolicy::CacheFor { .. } = self.negative_cache {
+                    self.errors.insert(arg, (e.clone(), now));
+                }
+                Err(e)
+            }
+        }
+    }
+
+    // Number of calls answered from the cache, including cached errors
+    #[allow(dead_code)]
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    // Number of calls that had to run the calculation
+    pub fn misses(&self) -> u64 {
+        self.misses
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkoutError {
+    ZeroIntensity,
+    IntensityTooHigh(u32),
+}
+
+impl fmt::Display for WorkoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WorkoutError::ZeroIntensity => write!(f, "intensity must be at least 1"),
+            WorkoutError::IntensityTooHigh(intensity) => {
+                write!(f, "intensity {} is above the maximum of 100", intensity)
+            }
+        }
+    }
+}
+
+fn simulated_expensive_calculation(intensity: u32) -> Result<u32, WorkoutError> {
+    println!("calculating slowly...");
+    thread::sleep(Duration::from_secs(2));
+
+    match intensity {
+        0 => Err(WorkoutError::ZeroIntensity),
+        1..=100 => Ok(intensity),
+        _ => Err(WorkoutError::IntensityTooHigh(intensity)),
+    }
+}
+
+// How likely a high intensity day is turned into a rest day unless configured otherwise
+pub const DEFAULT_REST_DAY_PROBABILITY: f64 = 0.1;
+
+// Keeps the slow calculation's results around between plans, so generating plans for many
+// days or users only pays for each distinct intensity once.
+pub struct WorkoutGenerator<T>
+where
+    T: Fn(u32) -> Result<u32, WorkoutError>,
+{
+    expensive_result: FallibleCacher<T, u32, u32, WorkoutError>,
+    rng: Rng,
+    rest_day_probability: f64,
+}
+
+impl WorkoutGenerator<fn(u32) -> Result<u32, WorkoutError>> {
+    pub fn new() -> WorkoutGenerator<fn(u32) -> Result<u32, WorkoutError>> {
+        WorkoutGenerator::with_calculation(simulated_expensive_calculation, Rng::from_time())
+    }
+}
+
+impl<T> WorkoutGenerator<T>
+where
+    T: Fn(u32) -> Result<u32, WorkoutError>,
+{
+    // Pass `Rng::seeded` to get the same plans on every run
+    pub fn with_calculation(calculation: T, rng: Rng) -> WorkoutGenerator<T> {
+        WorkoutGenerator {
+            expensive_result: FallibleCacher::new(calculation),
+            rng,
+            rest_day_probability: DEFAULT_REST_DAY_PROBABILITY,
+        }
+    }
+
+    // Probability between 0 and 1 that a high intensity day becomes a rest day
+    #[allow(dead_code)]
+    pub fn set_rest_day_probability(&mut self, probability: f64) {
+        self.rest_day_probability = probability.clamp(0.0, 1.0);
+    }
+
+    // Falls back to an easy plan when the calculation fails instead of panicking
+    pub fn generate(&mut self, profile: &UserProfile, intensity: u32) -> WorkoutPlan {
+        match self.expensive_result.value(intensity) {
+            Ok(intensity) => {
+                let rest_day = intensity >= workout::HIGH_INTENSITY
+                    && self.rng.chance(self.rest_day_probability);
+                workout::plan_workout(profile, intensity, rest_day)
+            }
+            Err(e) => WorkoutPlan::fallback(&format!("Could not calculate today's workout: {}", e)),
+        }
+    }
+
+    // Number of times the slow calculation actually ran
+    #[allow(dead_code)]
+    pub fn calculations_run(&self) -> u64 {
+        self.expensive_result.misses()
+    }
+}
+
+pub fn generate_workout(profile: &UserProfile, intensity: u32) -> WorkoutPlan {
+    WorkoutGenerator::new().generate(profile, intensity)
+}
+
 #[test]
 fn call_cacher_with_different_values() {
     let mut c = Cacher::new(|a| a);
@@ -92,3 +569,378 @@ fn call_cacher_with_different_values() {
 
     assert_eq!(v2, 2);
 }
+
+#[test]
+fn cacher_runs_calculation_once_per_distinct_arg() {
+    use std::cell::Cell;
+
+    let calls = Cell::new(0);
+    let mut c = Cacher::new(|a: u32| {
+        calls.set(calls.get() + 1);
+        a * 2
+    });
+
+    assert_eq!(c.value(4), 8);
+    assert_eq!(c.value(10), 20);
+    assert_eq!(c.value(4), 8);
+
+    assert_eq!(calls.get(), 2);
+    assert_eq!(c.misses(), 2);
+    assert_eq!(c.hits(), 1);
+}
+
+#[test]
+fn cacher_works_with_non_integer_keys() {
+    let mut c = Cacher::new(|s: String| s.len());
+
+    assert_eq!(c.value(String::from("hello")), 5);
+    assert_eq!(c.value(String::from("hello")), 5);
+    assert_eq!(c.hits(), 1);
+}
+
+#[cfg(test)]
+struct FakeClock {
+    now: std::rc::Rc<std::cell::Cell<Duration>>,
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+#[test]
+fn lru_cacher_drops_least_recently_used_value() {
+    let mut c = Cacher::with_policy(|a: u32| a, EvictionPolicy::Lru { capacity: 2 });
+
+    c.value(1);
+    c.value(2);
+    // Touch 1 so that 2 becomes the least recently used value
+    c.value(1);
+    c.value(3);
+
+    assert_eq!(c.len(), 2);
+
+    c.value(1);
+    assert_eq!(c.hits(), 2);
+
+    c.value(2);
+    assert_eq!(c.misses(), 4);
+}
+
+#[test]
+fn ttl_cacher_recalculates_expired_values() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let now = Rc::new(Cell::new(Duration::from_secs(0)));
+    let clock = FakeClock {
+        now: Rc::clone(&now),
+    };
+    let mut c = Cacher::with_policy_and_clock(
+        |a: u32| a,
+        EvictionPolicy::Ttl {
+            ttl: Duration::from_secs(60),
+        },
+        Box::new(clock),
+    );
+
+    c.value(1);
+    now.set(Duration::from_secs(59));
+    c.value(1);
+    assert_eq!(c.hits(), 1);
+
+    now.set(Duration::from_secs(60));
+    c.value(1);
+    assert_eq!(c.misses(), 2);
+}
+
+#[test]
+fn ttl_cacher_survives_a_clock_going_backwards() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let now = Rc::new(Cell::new(Duration::from_secs(100)));
+    let clock = FakeClock {
+        now: Rc::clone(&now),
+    };
+    let mut c = Cacher::with_policy_and_clock(
+        |a: u32| a,
+        EvictionPolicy::Ttl {
+            ttl: Duration::from_secs(60),
+        },
+        Box::new(clock),
+    );
+
+    c.value(1);
+    now.set(Duration::from_secs(40));
+    c.value(1);
+    c.value(2);
+    assert_eq!(c.hits(), 1);
+    assert_eq!(c.len(), 2);
+
+    now.set(Duration::from_secs(160));
+    c.value(1);
+    assert_eq!(c.misses(), 3);
+}
+
+#[test]
+fn ttl_cacher_purges_expired_values_on_insert() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let now = Rc::new(Cell::new(Duration::from_secs(0)));
+    let clock = FakeClock {
+        now: Rc::clone(&now),
+    };
+    let mut c = Cacher::with_policy_and_clock(
+        |a: u32| a,
+        EvictionPolicy::Ttl {
+            ttl: Duration::from_secs(10),
+        },
+        Box::new(clock),
+    );
+
+    c.value(1);
+    c.value(2);
+    now.set(Duration::from_secs(5));
+    c.value(3);
+    now.set(Duration::from_secs(12));
+    c.value(4);
+    assert_eq!(c.len(), 2);
+    assert_eq!(c.order.len(), 2);
+
+    now.set(Duration::from_secs(30));
+    c.value(5);
+
+    assert_eq!(c.len(), 1);
+    assert_eq!(c.order.len(), 1);
+}
+
+#[test]
+fn lru_cacher_keeps_its_recency_order_in_step() {
+    let mut c = Cacher::with_policy(|a: u32| a, EvictionPolicy::Lru { capacity: 3 });
+
+    for a in [1, 2, 3, 1, 4, 2, 5, 1, 1, 6].iter() {
+        c.value(*a);
+        assert_eq!(c.order.len(), c.len());
+    }
+
+    // 1 and 6 were used last, 5 before them
+    let kept: Vec<u32> = c.order.values().copied().collect();
+    assert_eq!(kept, vec![5, 1, 6]);
+    assert_eq!(c.hits(), 2);
+}
+
+#[test]
+fn shared_cacher_runs_calculation_once_for_concurrent_callers() {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Barrier;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted_calls = Arc::clone(&calls);
+    let cacher = Arc::new(SharedCacher::new(move |a: u32| {
+        counted_calls.fetch_add(1, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(50));
+        a + 1
+    }));
+    let barrier = Arc::new(Barrier::new(8));
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let cacher = Arc::clone(&cacher);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                cacher.value(41)
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), 42);
+    }
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(cacher.misses(), 1);
+    assert_eq!(cacher.hits(), 7);
+}
+
+#[test]
+fn shared_cacher_computes_distinct_keys_separately() {
+    let cacher = Arc::new(SharedCacher::new(|a: u32| a * 10));
+
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let cacher = Arc::clone(&cacher);
+            thread::spawn(move || cacher.value(i % 2))
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(cacher.misses(), 2);
+    assert_eq!(cacher.value(1), 10);
+}
+
+#[test]
+fn shared_cacher_recovers_when_calculation_panics() {
+    use std::sync::atomic::AtomicBool;
+
+    let fail = Arc::new(AtomicBool::new(true));
+    let should_fail = Arc::clone(&fail);
+    let cacher = Arc::new(SharedCacher::new(move |a: u32| {
+        if should_fail.swap(false, Ordering::SeqCst) {
+            panic!("calculation failed");
+        }
+        a
+    }));
+
+    let panicking = Arc::clone(&cacher);
+    assert!(thread::spawn(move || panicking.value(7)).join().is_err());
+
+    assert_eq!(cacher.value(7), 7);
+}
+
+#[test]
+fn fallible_cacher_does_not_cache_errors_by_default() {
+    use std::cell::Cell;
+
+    let calls = Cell::new(0);
+    let mut c = FallibleCacher::new(|a: u32| {
+        calls.set(calls.get() + 1);
+        if a == 0 {
+            Err(WorkoutError::ZeroIntensity)
+        } else {
+            Ok(a)
+        }
+    });
+
+    assert_eq!(c.value(0), Err(WorkoutError::ZeroIntensity));
+    assert_eq!(c.value(0), Err(WorkoutError::ZeroIntensity));
+    assert_eq!(c.value(5), Ok(5));
+    assert_eq!(c.value(5), Ok(5));
+
+    assert_eq!(calls.get(), 3);
+}
+
+#[test]
+fn fallible_cacher_caches_errors_for_negative_cache_ttl() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let calls = Cell::new(0);
+    let now = Rc::new(Cell::new(Duration::from_secs(0)));
+    let clock = FakeClock {
+        now: Rc::clone(&now),
+    };
+    let mut c = FallibleCacher::with_negative_cache_and_clock(
+        |a: u32| {
+            calls.set(calls.get() + 1);
+            Err::<u32, _>(WorkoutError::IntensityTooHigh(a))
+        },
+        NegativeCachePolicy::CacheFor {
+            ttl: Duration::from_secs(30),
+        },
+        Box::new(clock),
+    );
+
+    assert!(c.value(500).is_err());
+    now.set(Duration::from_secs(10));
+    assert!(c.value(500).is_err());
+    assert_eq!(calls.get(), 1);
+
+    now.set(Duration::from_secs(30));
+    assert_eq!(c.value(500), Err(WorkoutError::IntensityTooHigh(500)));
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn fallible_cacher_survives_a_clock_going_backwards() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let calls = Cell::new(0);
+    let now = Rc::new(Cell::new(Duration::from_secs(100)));
+    let clock = FakeClock {
+        now: Rc::clone(&now),
+    };
+    let mut c = FallibleCacher::with_negative_cache_and_clock(
+        |a: u32| {
+            calls.set(calls.get() + 1);
+            Err::<u32, _>(WorkoutError::IntensityTooHigh(a))
+        },
+        NegativeCachePolicy::CacheFor {
+            ttl: Duration::from_secs(30),
+        },
+        Box::new(clock),
+    );
+
+    assert!(c.value(500).is_err());
+    now.set(Duration::from_secs(50));
+    assert!(c.value(500).is_err());
+    assert_eq!(calls.get(), 1);
+
+    now.set(Duration::from_secs(130));
+    assert!(c.value(500).is_err());
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn workout_generator_runs_calculation_once_per_distinct_intensity() {
+    let mut generator = WorkoutGenerator::with_calculation(Ok, Rng::seeded(1));
+    let profile = UserProfile::new(30, 70.0, 1.75);
+
+    generator.generate(&profile, 10);
+    generator.generate(&profile, 30);
+    generator.generate(&profile, 10);
+
+    assert_eq!(generator.calculations_run(), 2);
+}
+
+#[test]
+fn workout_generator_returns_fallback_plan_on_error() {
+    let mut generator = WorkoutGenerator::with_calculation(
+        |intensity| Err(WorkoutError::IntensityTooHigh(intensity)),
+        Rng::seeded(1),
+    );
+    let profile = UserProfile::new(30, 70.0, 1.75);
+
+    let plan = generator.generate(&profile, 500);
+
+    assert_eq!(plan.exercises.len(), 1);
+    assert!(plan.notes[0].contains("intensity 500 is above the maximum of 100"));
+}
+
+#[test]
+fn workout_generator_is_reproducible_from_a_seed() {
+    let profile = UserProfile::new(30, 70.0, 1.75);
+    let mut a = WorkoutGenerator::with_calculation(Ok, Rng::seeded(2024));
+    let mut b = WorkoutGenerator::with_calculation(Ok, Rng::seeded(2024));
+    a.set_rest_day_probability(0.5);
+    b.set_rest_day_probability(0.5);
+
+    let plans_a: Vec<_> = (0..30).map(|_| a.generate(&profile, 40)).collect();
+    let plans_b: Vec<_> = (0..30).map(|_| b.generate(&profile, 40)).collect();
+
+    assert_eq!(plans_a, plans_b);
+    assert!(plans_a.iter().any(|plan| plan.is_rest_day()));
+    assert!(plans_a.iter().any(|plan| !plan.is_rest_day()));
+}
+
+#[test]
+fn workout_generator_rest_day_probability_bounds() {
+    let profile = UserProfile::new(30, 70.0, 1.75);
+    let mut generator = WorkoutGenerator::with_calculation(Ok, Rng::seeded(5));
+
+    generator.set_rest_day_probability(1.0);
+    assert!(generator.generate(&profile, 40).is_rest_day());
+    // Low intensity days are never turned into rest days
+    assert!(!generator.generate(&profile, 10).is_rest_day());
+
+    generator.set_rest_day_probability(0.0);
+    assert!((0..20).all(|_| !generator.generate(&profile, 40).is_rest_day()));
+}
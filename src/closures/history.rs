@@ -0,0 +1,294 @@
+// Keeps track of completed workouts in a plain text file so they survive a restart.
+// Every line is one exercise: `<day>,<exercise>,<intensity>`, where `day` counts days since
+// 1970-01-01. Empty lines and lines starting with `#` are ignored. The intensity of a session
+// is split over its exercises, so the lines of one day add up to the session intensity.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::workout::{Exercise, RecentWorkout, WorkoutPlan};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletedSession {
+    pub day: u64,
+    pub exercise: Exercise,
+    pub intensity: u32,
+}
+
+impl CompletedSession {
+    fn to_line(&self) -> String {
+        format!("{},{},{}\n", self.day, self.exercise, self.intensity)
+    }
+
+    fn from_line(line: &str) -> Option<CompletedSession> {
+        let mut parts = line.split(',');
+
+        let day = parts.next()?.trim().parse().ok()?;
+        let exercise = Exercise::from_name(parts.next()?.trim())?;
+        let intensity = parts.next()?.trim().parse().ok()?;
+
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(CompletedSession {
+            day,
+            exercise,
+            intensity,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum HistoryError {
+    Io(io::Error),
+    // `line` starts at 1
+    Parse { line: usize, content: String },
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HistoryError::Io(e) => write!(f, "{}", e),
+            HistoryError::Parse { line, content } => {
+                write!(f, "invalid workout history on line {}: {:?}", line, content)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for HistoryError {
+    fn from(e: io::Error) -> HistoryError {
+        HistoryError::Io(e)
+    }
+}
+
+pub struct HistoryStore {
+    path: PathBuf,
+    sessions: Vec<CompletedSession>,
+}
+
+impl HistoryStore {
+    // Loads the sessions stored in `path`. A missing file is an empty history, it is created
+    // on the first `record`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<HistoryStore, HistoryError> {
+        let path = path.as_ref().to_path_buf();
+        let contents = match read_history_file(&path) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut sessions = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match CompletedSession::from_line(line) {
+                Some(session) => sessions.push(session),
+                None => {
+                    return Err(HistoryError::Parse {
+                        line: index + 1,
+                        content: String::from(line),
+                    })
+                }
+            }
+        }
+
+        Ok(HistoryStore { path, sessions })
+    }
+
+    // Appends the session to the file and keeps it in memory
+    pub fn record(&mut self, session: CompletedSession) -> Result<(), HistoryError> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(session.to_line().as_bytes())?;
+
+        self.sessions.push(session);
+        Ok(())
+    }
+
+    // Records every exercise of a plan done on `day`, rest days record nothing. The plan
+    // intensity is shared between the exercises so `recent_load` counts it once.
+    pub fn record_plan(&mut self, day: u64, plan: &WorkoutPlan) -> Result<(), HistoryError> {
+        let count = plan.exercises.len() as u32;
+        for (index, exercise) in plan.exercises.iter().enumerate() {
+            let remainder = u32::from((index as u32) < plan.intensity % count);
+            self.record(CompletedSession {
+                day,
+                exercise: exercise.exercise,
+                intensity: plan.intensity / count + remainder,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    pub fn sessions(&self) -> &[CompletedSession] {
+        &self.sessions
+    }
+
+    // Sessions from the last `days` days, ready to be put into `UserProfile::recent_workouts`
+    pub fn recent_workouts(&self, today: u64, days: u64) -> Vec<RecentWorkout> {
+        self.sessions
+            .iter()
+            .filter(|session| session.day <= today && today - session.day < days)
+            .map(|session| RecentWorkout {
+                days_ago: (today - session.day) as u32,
+                exercise: session.exercise,
+                intensity: session.intensity,
+            })
+            .collect()
+    }
+}
+
+fn read_history_file(path: &Path) -> Result<String, io::Error> {
+    let mut s = String::new();
+
+    File::open(path)?.read_to_string(&mut s)?;
+
+    Ok(s)
+}
+
+// Days since 1970-01-01, the `day` used in the history file
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / (24 * 60 * 60))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::workout::{PlannedExercise, UserProfile};
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::time::Duration;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!(
+            "workout_history_{}_{}.txt",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn recorded_sessions_are_reloaded() {
+        let path = temp_path("reload");
+
+        let mut store = HistoryStore::open(&path).unwrap();
+        assert!(store.sessions().is_empty());
+
+        store
+            .record(CompletedSession {
+                day: 100,
+                exercise: Exercise::Running,
+                intensity: 30,
+            })
+            .unwrap();
+        store
+            .record(CompletedSession {
+                day: 101,
+                exercise: Exercise::Yoga,
+                intensity: 12,
+            })
+            .unwrap();
+
+        let reloaded = HistoryStore::open(&path).unwrap();
+        assert_eq!(reloaded.sessions(), store.sessions());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn recent_workouts_only_include_the_last_days() {
+        let path = temp_path("recent");
+        fs::write(
+            &path,
+            "# day,exercise,intensity\n90,running,40\n98,pushups,10\n\n100,yoga,12\n",
+        )
+        .unwrap();
+
+        let store = HistoryStore::open(&path).unwrap();
+        let recent = store.recent_workouts(100, 3);
+
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].days_ago, 2);
+        assert_eq!(recent[0].exercise, Exercise::Pushups);
+        assert_eq!(recent[1].days_ago, 0);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn recorded_plans_keep_their_load_after_a_reload() {
+        let path = temp_path("plan");
+        let plan = WorkoutPlan {
+            intensity: 30,
+            exercises: [
+                Exercise::Pushups,
+                Exercise::Situps,
+                Exercise::Squats,
+                Exercise::Running,
+            ]
+            .iter()
+            .map(|exercise| PlannedExercise {
+                exercise: *exercise,
+                sets: 1,
+                reps: 10,
+                duration: Duration::from_secs(0),
+            })
+            .collect(),
+            rest_days: 0,
+            notes: Vec::new(),
+        };
+
+        let mut profile = UserProfile::new(30, 70.0, 1.75);
+        profile.recent_workouts = vec![RecentWorkout {
+            days_ago: 0,
+            exercise: Exercise::Pushups,
+            intensity: plan.intensity,
+        }];
+        let before = profile.recent_load(2);
+
+        let mut store = HistoryStore::open(&path).unwrap();
+        store.record_plan(100, &plan).unwrap();
+        store
+            .record_plan(101, &WorkoutPlan::rest_day("rest"))
+            .unwrap();
+
+        profile.recent_workouts = HistoryStore::open(&path).unwrap().recent_workouts(100, 2);
+        assert_eq!(profile.recent_workouts.len(), 4);
+        assert_eq!(profile.recent_load(2), before);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn malformed_lines_are_reported() {
+        let path = temp_path("malformed");
+        fs::write(&path, "100,running,30\n101,juggling,5\n").unwrap();
+
+        match HistoryStore::open(&path) {
+            Err(HistoryError::Parse { line, content }) => {
+                assert_eq!(line, 2);
+                assert_eq!(content, "101,juggling,5");
+            }
+            _ => panic!("expected a parse error"),
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+}
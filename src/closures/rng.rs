@@ -0,0 +1,81 @@
+// A small seedable pseudo random number generator (SplitMix64). Good enough to vary workout
+// plans from day to day; not meant for anything security related.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    // The same seed always produces the same sequence, which keeps tests reproducible
+    pub fn seeded(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    // Seeded from the current time, so every run produces different numbers
+    pub fn from_time() -> Rng {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+
+        Rng::seeded(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniformly distributed in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Returns true with the given probability, e.g. `chance(0.25)` is true one time in four
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_sequence() {
+        let mut a = Rng::seeded(42);
+        let mut b = Rng::seeded(42);
+
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_sequences() {
+        let mut a = Rng::seeded(1);
+        let mut b = Rng::seeded(2);
+
+        assert_ne!(a.next_u64(), b.next_u64());
+    }
+
+    #[test]
+    fn chance_respects_probability_bounds() {
+        let mut rng = Rng::seeded(7);
+
+        for _ in 0..100 {
+            assert!(!rng.chance(0.0));
+            assert!(rng.chance(1.0));
+        }
+
+        let hits = (0..10_000).filter(|_| rng.chance(0.3)).count();
+        assert!(hits > 2_700 && hits < 3_300);
+    }
+}
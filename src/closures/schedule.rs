@@ -0,0 +1,264 @@
+// Multi-week training schedules. Intensity goes up a little every week (progressive overload),
+// every few weeks there is an easier deload week, and a week never goes above the maximum
+// volume. The day to day plans come from the `WorkoutGenerator`.
+
+use std::fmt;
+
+use super::workout::{UserProfile, WorkoutPlan};
+use super::{Cacher, WorkoutError, WorkoutGenerator};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleSettings {
+    pub weeks: u32,
+    // Training days per week, between 1 and 7
+    pub sessions_per_week: u32,
+    pub start_intensity: u32,
+    // Relative increase per week, 0.05 means 5% harder every week
+    pub weekly_increase: f64,
+    // Every `deload_every`th week is a deload week, 0 turns deload weeks off
+    pub deload_every: u32,
+    // Intensity multiplier used during deload weeks
+    pub deload_factor: f64,
+    // Upper limit for the combined intensity of all sessions in a week
+    pub max_weekly_volume: u32,
+}
+
+impl Default for ScheduleSettings {
+    fn default() -> ScheduleSettings {
+        ScheduleSettings {
+            weeks: 8,
+            sessions_per_week: 3,
+            start_intensity: 20,
+            weekly_increase: 0.05,
+            deload_every: 4,
+            deload_factor: 0.6,
+            max_weekly_volume: 150,
+        }
+    }
+}
+
+impl ScheduleSettings {
+    pub fn is_deload_week(&self, week: u32) -> bool {
+        self.deload_every > 0 && week.is_multiple_of(self.deload_every)
+    }
+
+    // Target intensity of a single session in `week` (starting at 1)
+    fn week_intensity(&self, week: u32) -> u32 {
+        let progression = (1.0 + self.weekly_increase).powi(week as i32 - 1);
+        let mut intensity = self.start_intensity as f64 * progression;
+
+        if self.is_deload_week(week) {
+            intensity *= self.deload_factor;
+        }
+
+        (intensity.round() as u32).max(1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub week: u32,
+    // Day of the week, 1 to 7
+    pub day: u32,
+    pub deload: bool,
+    pub plan: WorkoutPlan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub sessions: Vec<Session>,
+}
+
+impl Schedule {
+    // Combined intensity of all sessions in `week`
+    pub fn weekly_volume(&self, week: u32) -> u32 {
+        self.sessions
+            .iter()
+            .filter(|session| session.week == week)
+            .map(|session| session.plan.intensity)
+            .sum()
+    }
+
+    // Plain text calendar, one line per session
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let mut current_week = 0;
+
+        for session in &self.sessions {
+            if session.week != current_week {
+                current_week = session.week;
+                let deload = if session.deload { " (deload)" } else { "" };
+                out.push_str(&format!("Week {}{}\n", current_week, deload));
+            }
+
+            let summary = if session.plan.is_rest_day() {
+                String::from("rest day")
+            } else {
+                session
+                    .plan
+                    .exercises
+                    .iter()
+                    .map(|exercise| exercise.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            out.push_str(&format!("  Day {}: {}\n", session.day, summary));
+        }
+
+        out
+    }
+
+    // One row per exercise, rest days get a single `rest` row
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("week,day,deload,exercise,sets,reps,minutes\n");
+
+        for session in &self.sessions {
+            if session.plan.is_rest_day() {
+                out.push_str(&format!(
+                    "{},{},{},rest,0,0,0\n",
+                    session.week, session.day, session.deload
+                ));
+            }
+
+            for exercise in &session.plan.exercises {
+                out.push_str(&format!(
+                    "{},{},{},{},{},{},{}\n",
+                    session.week,
+                    session.day,
+                    session.deload,
+                    exercise.exercise,
+                    exercise.sets,
+                    exercise.reps,
+                    exercise.duration.as_secs() / 60
+                ));
+            }
+        }
+
+        out
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_text())
+    }
+}
+
+pub fn plan_schedule<T>(
+    generator: &mut WorkoutGenerator<T>,
+    profile: &UserProfile,
+    settings: &ScheduleSettings,
+) -> Schedule
+where
+    T: Fn(u32) -> Result<u32, WorkoutError>,
+{
+    let sessions_per_week = settings.sessions_per_week.clamp(1, 7);
+    let mut week_intensity = Cacher::new(|week: u32| settings.week_intensity(week));
+    let mut sessions = Vec::new();
+
+    for week in 1..=settings.weeks {
+        let deload = settings.is_deload_week(week);
+        let mut volume = 0;
+
+        for session in 0..sessions_per_week {
+            let remaining = settings.max_weekly_volume.saturating_sub(volume);
+            let plan = if remaining == 0 {
+                WorkoutPlan::rest_day("Weekly volume limit reached, rest until next week!")
+            } else {
+                generator.generate(profile, week_intensity.value(week).min(remaining))
+            };
+
+            volume += plan.intensity;
+            sessions.push(Session {
+                week,
+                // Spread the sessions evenly over the week
+                day: 1 + session * 7 / sessions_per_week,
+                deload,
+                plan,
+            });
+        }
+    }
+
+    Schedule { sessions }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::rng::Rng;
+    use super::*;
+
+    fn schedule(settings: &ScheduleSettings) -> Schedule {
+        let mut generator = WorkoutGenerator::with_calculation(Ok, Rng::seeded(3));
+        generator.set_rest_day_probability(0.0);
+
+        plan_schedule(&mut generator, &UserProfile::new(30, 70.0, 1.75), settings)
+    }
+
+    #[test]
+    fn intensity_increases_every_week_except_deload_weeks() {
+        let settings = ScheduleSettings {
+            weeks: 4,
+            start_intensity: 20,
+            weekly_increase: 0.1,
+            max_weekly_volume: 1_000,
+            ..ScheduleSettings::default()
+        };
+        let schedule = schedule(&settings);
+
+        let first_session_of = |week: u32| {
+            schedule
+                .sessions
+                .iter()
+                .find(|session| session.week == week)
+                .unwrap()
+        };
+
+        assert_eq!(first_session_of(1).plan.intensity, 20);
+        assert_eq!(first_session_of(2).plan.intensity, 22);
+        assert_eq!(first_session_of(3).plan.intensity, 24);
+        assert!(first_session_of(4).deload);
+        assert_eq!(first_session_of(4).plan.intensity, 16);
+        assert_eq!(schedule.sessions.len(), 12);
+    }
+
+    #[test]
+    fn weekly_volume_never_exceeds_maximum() {
+        let settings = ScheduleSettings {
+            weeks: 6,
+            sessions_per_week: 5,
+            start_intensity: 40,
+            max_weekly_volume: 100,
+            ..ScheduleSettings::default()
+        };
+        let schedule = schedule(&settings);
+
+        for week in 1..=6 {
+            assert!(schedule.weekly_volume(week) <= 100);
+        }
+        assert!(schedule.sessions.iter().any(|s| s.plan.is_rest_day()));
+    }
+
+    #[test]
+    fn schedule_can_be_written_as_csv_and_text() {
+        let settings = ScheduleSettings {
+            weeks: 1,
+            sessions_per_week: 2,
+            start_intensity: 10,
+            ..ScheduleSettings::default()
+        };
+        let schedule = schedule(&settings);
+
+        let csv = schedule.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("week,day,deload,exercise,sets,reps,minutes")
+        );
+        assert_eq!(lines.next(), Some("1,1,false,pushups,1,10,0"));
+        assert_eq!(csv.lines().count(), 5);
+
+        let text = schedule.to_text();
+        assert!(text.starts_with("Week 1\n  Day 1: pushups: 1 x 10 reps"));
+        assert!(text.contains("  Day 4: "));
+    }
+}
@@ -0,0 +1,440 @@
+// The workout plan model and the algorithm that fills it in from a user's profile.
+// `closures::generate_workout` runs the slow calculation through a cacher and then hands the
+// result to `plan_workout` below.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::time::Duration;
+
+// Intensities from this value upwards get a cardio plan when the user has no preferences
+// and may randomly be turned into a rest day
+pub const HIGH_INTENSITY: u32 = 25;
+
+// Combined intensity of the last two days above which we prescribe a recovery day
+const RECOVERY_LOAD: u32 = 120;
+
+pub const ALL_EXERCISES: [Exercise; 8] = [
+    Exercise::Pushups,
+    Exercise::Situps,
+    Exercise::Squats,
+    Exercise::Running,
+    Exercise::Cycling,
+    Exercise::Walking,
+    Exercise::Yoga,
+    Exercise::Stretching,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Exercise {
+    Pushups,
+    Situps,
+    Squats,
+    Running,
+    Cycling,
+    Walking,
+    Yoga,
+    Stretching,
+}
+
+impl Exercise {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Exercise::Pushups => "pushups",
+            Exercise::Situps => "situps",
+            Exercise::Squats => "squats",
+            Exercise::Running => "running",
+            Exercise::Cycling => "cycling",
+            Exercise::Walking => "walking",
+            Exercise::Yoga => "yoga",
+            Exercise::Stretching => "stretching",
+        }
+    }
+
+    // The inverse of `name`
+    pub fn from_name(name: &str) -> Option<Exercise> {
+        ALL_EXERCISES
+            .iter()
+            .find(|exercise| exercise.name() == name)
+            .copied()
+    }
+
+    // Timed exercises are planned in minutes, the others in repetitions
+    pub fn is_timed(&self) -> bool {
+        !matches!(
+            self,
+            Exercise::Pushups | Exercise::Situps | Exercise::Squats
+        )
+    }
+
+    // Easier on the joints, used for users with a high body mass index
+    fn low_impact_alternative(self) -> Exercise {
+        match self {
+            Exercise::Running => Exercise::Cycling,
+            Exercise::Squats => Exercise::Walking,
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for Exercise {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preference {
+    Strength,
+    Cardio,
+    Flexibility,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecentWorkout {
+    pub days_ago: u32,
+    pub exercise: Exercise,
+    pub intensity: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserProfile {
+    pub age: u32,
+    pub weight_kg: f64,
+    pub height_m: f64,
+    pub preferences: Vec<Preference>,
+    pub recent_workouts: Vec<RecentWorkout>,
+}
+
+impl UserProfile {
+    pub fn new(age: u32, weight_kg: f64, height_m: f64) -> UserProfile {
+        UserProfile {
+            age,
+            weight_kg,
+            height_m,
+            preferences: Vec::new(),
+            recent_workouts: Vec::new(),
+        }
+    }
+
+    pub fn bmi(&self) -> f64 {
+        self.weight_kg / (self.height_m * self.height_m)
+    }
+
+    // Combined intensity of the workouts done within the last `days` days
+    pub fn recent_load(&self, days: u32) -> u32 {
+        self.recent_workouts
+            .iter()
+            .filter(|w| w.days_ago < days)
+            .map(|w| w.intensity)
+            .sum()
+    }
+
+    fn did_yesterday(&self, exercise: Exercise) -> bool {
+        self.recent_workouts
+            .iter()
+            .any(|w| w.days_ago <= 1 && w.exercise == exercise)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedExercise {
+    pub exercise: Exercise,
+    pub sets: u32,
+    // Repetitions per set, zero for timed exercises
+    pub reps: u32,
+    // Time per set, zero for repetition based exercises
+    pub duration: Duration,
+}
+
+impl fmt::Display for PlannedExercise {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.exercise.is_timed() {
+            write!(
+                f,
+                "{}: {} x {} minutes",
+                self.exercise,
+                self.sets,
+                self.duration.as_secs() / 60
+            )
+        } else {
+            write!(f, "{}: {} x {} reps", self.exercise, self.sets, self.reps)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkoutPlan {
+    pub intensity: u32,
+    pub exercises: Vec<PlannedExercise>,
+    // Days to rest after this session
+    pub rest_days: u32,
+    pub notes: Vec<String>,
+}
+
+impl WorkoutPlan {
+    pub fn rest_day(note: &str) -> WorkoutPlan {
+        WorkoutPlan {
+            intensity: 0,
+            exercises: Vec::new(),
+            rest_days: 0,
+            notes: vec![String::from(note)],
+        }
+    }
+
+    // An easy walk, used when the real plan cannot be calculated
+    pub fn fallback(note: &str) -> WorkoutPlan {
+        WorkoutPlan {
+            intensity: 0,
+            exercises: vec![PlannedExercise {
+                exercise: Exercise::Walking,
+                sets: 1,
+                reps: 0,
+                duration: Duration::from_secs(20 * 60),
+            }],
+            rest_days: 0,
+            notes: vec![
+                String::from(note),
+                String::from("Stretch afterwards and try again tomorrow!"),
+            ],
+        }
+    }
+
+    pub fn is_rest_day(&self) -> bool {
+        self.exercises.is_empty()
+    }
+}
+
+impl fmt::Display for WorkoutPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_rest_day() {
+            writeln!(f, "Rest day")?;
+        } else {
+            writeln!(f, "Workout plan (intensity {})", self.intensity)?;
+            for exercise in &self.exercises {
+                writeln!(f, "  - {}", exercise)?;
+            }
+            writeln!(f, "Rest days afterwards: {}", self.rest_days)?;
+        }
+
+        for note in &self.notes {
+            writeln!(f, "Note: {}", note)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Builds the plan for one day. `intensity` is the result of the slow calculation and
+// `rest_day` is the generator's random decision to take a break today.
+pub fn plan_workout(profile: &UserProfile, intensity: u32, rest_day: bool) -> WorkoutPlan {
+    if profile.recent_load(2) >= RECOVERY_LOAD {
+        return WorkoutPlan::rest_day(
+            "You trained hard in the last two days, let your body recover!",
+        );
+    }
+
+    if rest_day {
+        return WorkoutPlan::rest_day("Take a break today! Remember to stay hydrated!");
+    }
+
+    let intensity = adjusted_intensity(profile, intensity);
+    let mut notes = Vec::new();
+    let mut exercises = Vec::new();
+
+    for exercise in choose_exercises(profile, intensity) {
+        if profile.did_yesterday(exercise) {
+            notes.push(format!(
+                "Skipping {}, you already did it yesterday",
+                exercise
+            ));
+            continue;
+        }
+        exercises.push(plan_exercise(exercise, intensity));
+    }
+
+    if exercises.is_empty() {
+        exercises.push(plan_exercise(Exercise::Walking, intensity));
+    }
+
+    WorkoutPlan {
+        intensity,
+        exercises,
+        rest_days: if intensity >= 75 { 1 } else { 0 },
+        notes,
+    }
+}
+
+// Older users and users with a high body mass index get an easier workout
+fn adjusted_intensity(profile: &UserProfile, intensity: u32) -> u32 {
+    let mut factor = 1.0;
+
+    if profile.age >= 65 {
+        factor *= 0.6;
+    } else if profile.age >= 50 {
+        factor *= 0.8;
+    }
+
+    if profile.bmi() >= 30.0 {
+        factor *= 0.85;
+    }
+
+    ((intensity as f64 * factor).round() as u32).max(1)
+}
+
+fn choose_exercises(profile: &UserProfile, intensity: u32) -> Vec<Exercise> {
+    let preferences = if profile.preferences.is_empty() {
+        if intensity < HIGH_INTENSITY {
+            vec![Preference::Strength]
+        } else {
+            vec![Preference::Cardio]
+        }
+    } else {
+        profile.preferences.clone()
+    };
+
+    let mut exercises = Vec::new();
+    for preference in preferences {
+        match preference {
+            Preference::Strength if intensity < HIGH_INTENSITY => {
+                exercises.extend_from_slice(&[Exercise::Pushups, Exercise::Situps])
+            }
+            Preference::Strength => exercises.extend_from_slice(&[
+                Exercise::Pushups,
+                Exercise::Situps,
+                Exercise::Squats,
+            ]),
+            Preference::Cardio => exercises.push(Exercise::Running),
+            Preference::Flexibility => {
+                exercises.extend_from_slice(&[Exercise::Yoga, Exercise::Stretching])
+            }
+        }
+    }
+
+    if profile.bmi() >= 30.0 {
+        exercises = exercises
+            .into_iter()
+            .map(Exercise::low_impact_alternative)
+            .collect();
+    }
+
+    // Preferences can lead to the same exercise more than once, keep the first of each
+    let mut seen = HashSet::new();
+    exercises.retain(|exercise| seen.insert(*exercise));
+    exercises
+}
+
+fn plan_exercise(exercise: Exercise, intensity: u32) -> PlannedExercise {
+    match exercise {
+        Exercise::Yoga | Exercise::Stretching => PlannedExercise {
+            exercise,
+            sets: 1,
+            reps: 0,
+            duration: Duration::from_secs(60 * u64::from(10 + intensity / 5)),
+        },
+        _ if exercise.is_timed() => PlannedExercise {
+            exercise,
+            sets: 1,
+            reps: 0,
+            duration: Duration::from_secs(60 * u64::from(intensity)),
+        },
+        _ => {
+            let sets = (1 + intensity / HIGH_INTENSITY).min(5);
+            PlannedExercise {
+                exercise,
+                sets,
+                reps: (intensity / sets).max(1),
+                duration: Duration::from_secs(0),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> UserProfile {
+        UserProfile::new(30, 70.0, 1.75)
+    }
+
+    #[test]
+    fn low_intensity_without_preferences_is_pushups_and_situps() {
+        let plan = plan_workout(&profile(), 10, false);
+
+        assert_eq!(plan.exercises.len(), 2);
+        assert_eq!(plan.exercises[0].exercise, Exercise::Pushups);
+        assert_eq!(plan.exercises[0].reps, 10);
+        assert_eq!(plan.exercises[1].exercise, Exercise::Situps);
+    }
+
+    #[test]
+    fn high_intensity_without_preferences_is_a_run() {
+        let plan = plan_workout(&profile(), 30, false);
+
+        assert_eq!(plan.exercises[0].exercise, Exercise::Running);
+        assert_eq!(plan.exercises[0].duration, Duration::from_secs(30 * 60));
+    }
+
+    #[test]
+    fn rest_day_plan_has_no_exercises() {
+        let plan = plan_workout(&profile(), 30, true);
+
+        assert!(plan.is_rest_day());
+        assert_eq!(plan.notes.len(), 1);
+    }
+
+    #[test]
+    fn older_users_with_high_bmi_get_easier_low_impact_plans() {
+        let mut user = UserProfile::new(70, 100.0, 1.70);
+        user.preferences = vec![Preference::Cardio];
+
+        let plan = plan_workout(&user, 50, false);
+
+        assert_eq!(plan.intensity, 26);
+        assert_eq!(plan.exercises[0].exercise, Exercise::Cycling);
+    }
+
+    #[test]
+    fn repeated_exercises_are_only_planned_once() {
+        let mut user = profile();
+        user.preferences = vec![
+            Preference::Strength,
+            Preference::Cardio,
+            Preference::Strength,
+        ];
+
+        let exercises = choose_exercises(&user, 10);
+
+        assert_eq!(
+            exercises,
+            vec![Exercise::Pushups, Exercise::Situps, Exercise::Running]
+        );
+    }
+
+    #[test]
+    fn recent_workouts_are_taken_into_account() {
+        let mut user = profile();
+        user.preferences = vec![Preference::Strength, Preference::Flexibility];
+        user.recent_workouts.push(RecentWorkout {
+            days_ago: 1,
+            exercise: Exercise::Yoga,
+            intensity: 20,
+        });
+
+        let plan = plan_workout(&user, 10, false);
+        let exercises: Vec<_> = plan.exercises.iter().map(|e| e.exercise).collect();
+
+        assert_eq!(
+            exercises,
+            vec![Exercise::Pushups, Exercise::Situps, Exercise::Stretching]
+        );
+
+        user.recent_workouts.push(RecentWorkout {
+            days_ago: 0,
+            exercise: Exercise::Running,
+            intensity: 100,
+        });
+
+        assert!(plan_workout(&user, 10, false).is_rest_day());
+    }
+}
@@ -1,7 +1,415 @@
-pub mod enums {
-    #![allow(unused_variables)]
-    enum IpAddr {
-        V4(u8, u8, u8, u8),
-        V6(String),
+pub mod acl;
+pub mod collection;
+pub mod money;
+pub mod network;
+pub mod routing;
+pub mod wire;
+
+// An IP address is either version four or version six, but never both at the same time, which
+// makes it a good fit for an enum. Each variant stores its own kind of data.
+
+use std::fmt;
+use std::net;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IpAddr {
+    V4(u8, u8, u8, u8),
+    // The eight 16 bit groups, most significant first
+    V6([u16; 8]),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseIpError {
+    Empty,
+    InvalidV4(String),
+    InvalidV6(String),
+}
+
+impl fmt::Display for ParseIpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseIpError::Empty => write!(f, "empty IP address"),
+            ParseIpError::InvalidV4(s) => write!(f, "invalid IPv4 address {:?}", s),
+            ParseIpError::InvalidV6(s) => write!(f, "invalid IPv6 address {:?}", s),
+        }
+    }
+}
+
+impl IpAddr {
+    pub fn is_ipv4(&self) -> bool {
+        matches!(self, IpAddr::V4(..))
+    }
+
+    pub fn is_ipv6(&self) -> bool {
+        matches!(self, IpAddr::V6(_))
+    }
+
+    // Number of bits in the address
+    pub fn bit_len(&self) -> u8 {
+        match self {
+            IpAddr::V4(..) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+
+    // The address as one number, IPv4 only uses the lowest 32 bits
+    pub fn to_bits(self) -> u128 {
+        match self {
+            IpAddr::V4(a, b, c, d) => u128::from(u32::from_be_bytes([a, b, c, d])),
+            IpAddr::V6(segments) => segments
+                .iter()
+                .fold(0, |bits, segment| bits << 16 | u128::from(*segment)),
+        }
+    }
+
+    pub fn from_v4_bits(bits: u32) -> IpAddr {
+        let [a, b, c, d] = bits.to_be_bytes();
+        IpAddr::V4(a, b, c, d)
+    }
+
+    pub fn from_v6_bits(bits: u128) -> IpAddr {
+        let mut segments = [0; 8];
+        for (i, segment) in segments.iter_mut().enumerate() {
+            *segment = (bits >> (112 - 16 * i)) as u16;
+        }
+        IpAddr::V6(segments)
+    }
+
+    // 127.0.0.0/8 and ::1
+    pub fn is_loopback(&self) -> bool {
+        match self {
+            IpAddr::V4(a, ..) => *a == 127,
+            IpAddr::V6(segments) => *segments == [0, 0, 0, 0, 0, 0, 0, 1],
+        }
+    }
+
+    // 10.0.0.0/8, 172.16.0.0/12 and 192.168.0.0/16 for IPv4, unique local fc00::/7 for IPv6
+    pub fn is_private(&self) -> bool {
+        match self {
+            IpAddr::V4(a, b, ..) => {
+                *a == 10 || (*a == 172 && (16..32).contains(b)) || (*a == 192 && *b == 168)
+            }
+            IpAddr::V6(segments) => segments[0] & 0xfe00 == 0xfc00,
+        }
+    }
+
+    // 224.0.0.0/4 and ff00::/8
+    pub fn is_multicast(&self) -> bool {
+        match self {
+            IpAddr::V4(a, ..) => (224..240).contains(a),
+            IpAddr::V6(segments) => segments[0] & 0xff00 == 0xff00,
+        }
+    }
+
+    // 169.254.0.0/16 and fe80::/10
+    pub fn is_link_local(&self) -> bool {
+        match self {
+            IpAddr::V4(a, b, ..) => *a == 169 && *b == 254,
+            IpAddr::V6(segments) => segments[0] & 0xffc0 == 0xfe80,
+        }
+    }
+}
+
+impl FromStr for IpAddr {
+    type Err = ParseIpError;
+
+    // IPv4 in dotted quad form without leading zeros, IPv6 in any of the forms of RFC 4291:
+    // full, with `::` for a run of zero groups, or with a dotted quad in the last 32 bits
+    fn from_str(s: &str) -> Result<IpAddr, ParseIpError> {
+        if s.is_empty() {
+            Err(ParseIpError::Empty)
+        } else if s.contains(':') {
+            parse_v6(s)
+                .map(IpAddr::V6)
+                .ok_or_else(|| ParseIpError::InvalidV6(String::from(s)))
+        } else {
+            parse_v4(s)
+                .map(|[a, b, c, d]| IpAddr::V4(a, b, c, d))
+                .ok_or_else(|| ParseIpError::InvalidV4(String::from(s)))
+        }
+    }
+}
+
+fn parse_v4(s: &str) -> Option<[u8; 4]> {
+    let mut octets = [0; 4];
+    let mut parts = s.split('.');
+
+    for octet in octets.iter_mut() {
+        let part = parts.next()?;
+        let valid = !part.is_empty()
+            && part.len() <= 3
+            && part.bytes().all(|b| b.is_ascii_digit())
+            && (part == "0" || !part.starts_with('0'));
+        if !valid {
+            return None;
+        }
+        *octet = part.parse().ok()?;
+    }
+
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(octets)
+}
+
+fn parse_v6(s: &str) -> Option<[u16; 8]> {
+    let (head, tail) = match s.find("::") {
+        Some(i) => (&s[..i], Some(&s[i + 2..])),
+        None => (s, None),
+    };
+
+    let mut segments = [0; 8];
+    match tail {
+        None => {
+            let groups = parse_groups(head, true)?;
+            if groups.len() != 8 {
+                return None;
+            }
+            segments.copy_from_slice(&groups);
+        }
+        Some(tail) => {
+            let head = parse_groups(head, false)?;
+            let tail = parse_groups(tail, true)?;
+            // `::` stands for at least one zero group
+            if head.len() + tail.len() > 7 {
+                return None;
+            }
+            segments[..head.len()].copy_from_slice(&head);
+            segments[8 - tail.len()..].copy_from_slice(&tail);
+        }
+    }
+
+    Some(segments)
+}
+
+// Colon separated groups, the last one may be a dotted quad when `allow_v4` is set
+fn parse_groups(s: &str, allow_v4: bool) -> Option<Vec<u16>> {
+    let mut groups = Vec::new();
+    if s.is_empty() {
+        return Some(groups);
+    }
+
+    let parts: Vec<&str> = s.split(':').collect();
+    for (i, part) in parts.iter().enumerate() {
+        if allow_v4 && i == parts.len() - 1 && part.contains('.') {
+            let [a, b, c, d] = parse_v4(part)?;
+            groups.push(u16::from_be_bytes([a, b]));
+            groups.push(u16::from_be_bytes([c, d]));
+        } else if !part.is_empty() && part.len() <= 4 && part.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            groups.push(u16::from_str_radix(part, 16).ok()?);
+        } else {
+            return None;
+        }
+    }
+
+    Some(groups)
+}
+
+impl fmt::Display for IpAddr {
+    // IPv6 uses the canonical form of RFC 5952: lowercase, no leading zeros, and the longest
+    // run of two or more zero groups (the first one on a tie) written as `::`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let segments = match self {
+            IpAddr::V4(a, b, c, d) => return write!(f, "{}.{}.{}.{}", a, b, c, d),
+            IpAddr::V6(segments) => segments,
+        };
+
+        // IPv4 mapped addresses keep the dotted quad
+        if segments[..5] == [0; 5] && segments[5] == 0xffff {
+            let [a, b] = segments[6].to_be_bytes();
+            let [c, d] = segments[7].to_be_bytes();
+            return write!(f, "::ffff:{}.{}.{}.{}", a, b, c, d);
+        }
+
+        let mut longest = (0, 0);
+        let mut start = 0;
+        for (i, segment) in segments.iter().enumerate() {
+            if *segment != 0 {
+                start = i + 1;
+            } else if i + 1 - start > longest.1 - longest.0 {
+                longest = (start, i + 1);
+            }
+        }
+
+        let join = |groups: &[u16]| {
+            groups
+                .iter()
+                .map(|group| format!("{:x}", group))
+                .collect::<Vec<_>>()
+                .join(":")
+        };
+
+        if longest.1 - longest.0 >= 2 {
+            write!(
+                f,
+                "{}::{}",
+                join(&segments[..longest.0]),
+                join(&segments[longest.1..])
+            )
+        } else {
+            write!(f, "{}", join(segments))
+        }
+    }
+}
+
+impl From<net::Ipv4Addr> for IpAddr {
+    fn from(addr: net::Ipv4Addr) -> IpAddr {
+        let [a, b, c, d] = addr.octets();
+        IpAddr::V4(a, b, c, d)
+    }
+}
+
+impl From<net::Ipv6Addr> for IpAddr {
+    fn from(addr: net::Ipv6Addr) -> IpAddr {
+        IpAddr::V6(addr.segments())
+    }
+}
+
+impl From<net::IpAddr> for IpAddr {
+    fn from(addr: net::IpAddr) -> IpAddr {
+        match addr {
+            net::IpAddr::V4(addr) => addr.into(),
+            net::IpAddr::V6(addr) => addr.into(),
+        }
+    }
+}
+
+impl From<IpAddr> for net::IpAddr {
+    fn from(addr: IpAddr) -> net::IpAddr {
+        match addr {
+            IpAddr::V4(a, b, c, d) => net::IpAddr::V4(net::Ipv4Addr::new(a, b, c, d)),
+            IpAddr::V6(s) => net::IpAddr::V6(net::Ipv6Addr::new(
+                s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7],
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_both_versions() {
+        assert_eq!(ip("192.168.0.1"), IpAddr::V4(192, 168, 0, 1));
+        assert_eq!(ip("::1"), IpAddr::V6([0, 0, 0, 0, 0, 0, 0, 1]));
+        assert_eq!(ip("::"), IpAddr::V6([0; 8]));
+        assert_eq!(
+            ip("2001:DB8::8:800:200C:417A"),
+            IpAddr::V6([0x2001, 0xdb8, 0, 0, 0x8, 0x800, 0x200c, 0x417a])
+        );
+        assert_eq!(
+            ip("::ffff:10.0.0.1"),
+            IpAddr::V6([0, 0, 0, 0, 0, 0xffff, 0x0a00, 0x0001])
+        );
+        assert_eq!(ip("fe80::"), IpAddr::V6([0xfe80, 0, 0, 0, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn rejects_malformed_addresses() {
+        let invalid = [
+            "256.0.0.1",
+            "1.2.3",
+            "1.2.3.4.5",
+            "01.2.3.4",
+            "1..2.3",
+            "1.2.3.-4",
+            "1:2:3:4:5:6:7",
+            "1:2:3:4:5:6:7:8:9",
+            "1::2::3",
+            "1:2:3:4::5:6:7:8",
+            ":1::2",
+            "12345::",
+            "::g",
+            "1.2.3.4::",
+            "::1.2.3",
+        ];
+        for s in invalid.iter() {
+            assert!(s.parse::<IpAddr>().is_err(), "{} should not parse", s);
+            assert!(
+                s.parse::<net::IpAddr>().is_err(),
+                "std disagrees about {}",
+                s
+            );
+        }
+
+        assert_eq!("".parse::<IpAddr>(), Err(ParseIpError::Empty));
+        assert_eq!(
+            "1.2.3".parse::<IpAddr>().unwrap_err().to_string(),
+            "invalid IPv4 address \"1.2.3\""
+        );
+    }
+
+    #[test]
+    fn displays_the_canonical_form() {
+        let cases = [
+            ("2001:0db8:0000:0000:0000:0000:0002:0001", "2001:db8::2:1"),
+            ("2001:db8:0:1:1:1:1:1", "2001:db8:0:1:1:1:1:1"),
+            ("2001:0:0:1:0:0:0:1", "2001:0:0:1::1"),
+            ("2001:db8:0:0:1:0:0:1", "2001:db8::1:0:0:1"),
+            ("0:0:0:0:0:0:0:1", "::1"),
+            ("1:0:0:0:0:0:0:0", "1::"),
+            ("::ffff:192.0.2.128", "::ffff:192.0.2.128"),
+            ("10.0.0.1", "10.0.0.1"),
+        ];
+        for (input, expected) in cases.iter() {
+            let addr = ip(input);
+            assert_eq!(addr.to_string(), *expected);
+            assert_eq!(net::IpAddr::from(addr).to_string(), *expected);
+            assert_eq!(ip(expected), addr);
+        }
+    }
+
+    #[test]
+    fn classifies_addresses() {
+        assert!(ip("127.0.0.1").is_loopback());
+        assert!(ip("::1").is_loopback());
+        assert!(!ip("::2").is_loopback());
+
+        assert!(ip("10.1.2.3").is_private());
+        assert!(ip("172.31.0.1").is_private());
+        assert!(!ip("172.32.0.1").is_private());
+        assert!(ip("192.168.1.1").is_private());
+        assert!(ip("fd12:3456::1").is_private());
+        assert!(!ip("8.8.8.8").is_private());
+
+        assert!(ip("224.0.0.251").is_multicast());
+        assert!(ip("ff02::1").is_multicast());
+        assert!(!ip("240.0.0.1").is_multicast());
+
+        assert!(ip("169.254.10.1").is_link_local());
+        assert!(ip("febf::1").is_link_local());
+        assert!(!ip("fec0::1").is_link_local());
+
+        assert!(ip("1.1.1.1").is_ipv4());
+        assert!(ip("::").is_ipv6());
+    }
+
+    #[test]
+    fn converts_to_and_from_bits() {
+        assert_eq!(ip("10.0.0.1").to_bits(), 0x0a00_0001);
+        assert_eq!(IpAddr::from_v4_bits(0xc0a8_0001), ip("192.168.0.1"));
+        assert_eq!(ip("::1:2").to_bits(), 0x1_0002);
+        assert_eq!(
+            IpAddr::from_v6_bits(ip("2001:db8::ff00:42:8329").to_bits()),
+            ip("2001:db8::ff00:42:8329")
+        );
+    }
+
+    #[test]
+    fn converts_to_and_from_std() {
+        for s in ["0.0.0.0", "203.0.113.9", "::", "2001:db8::ff00:42:8329"].iter() {
+            let std_addr: net::IpAddr = s.parse().unwrap();
+            let addr = IpAddr::from(std_addr);
+
+            assert_eq!(addr, ip(s));
+            assert_eq!(net::IpAddr::from(addr), std_addr);
+        }
     }
 }
@@ -0,0 +1,414 @@
+// An access control list: ordered allow and deny rules over addresses, networks and address
+// ranges. Rules can be written in a text file, one per line:
+//
+//     # comments and empty lines are ignored
+//     mode longest-prefix
+//     default deny
+//     allow 10.0.0.0/8
+//     deny 10.0.13.37
+//     allow 192.168.1.10-192.168.1.20
+//     deny any
+//
+// `mode` is `first-match` (the default) or `longest-prefix`, `default` is the action when no
+// rule matches and is `deny` unless set.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::network::Network;
+use super::IpAddr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    // The first rule in the list that matches decides
+    FirstMatch,
+    // The most specific matching rule decides, the earlier one on a tie
+    LongestPrefix,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Any,
+    Network(Network),
+    // Both ends included, they need to be the same IP version
+    Range(IpAddr, IpAddr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub action: Action,
+    pub target: Target,
+}
+
+#[derive(Debug)]
+pub enum AclError {
+    Io(io::Error),
+    // `line` starts at 1
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for AclError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AclError::Io(e) => write!(f, "{}", e),
+            AclError::Parse { line, message } => {
+                write!(f, "invalid rule on line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for AclError {
+    fn from(e: io::Error) -> AclError {
+        AclError::Io(e)
+    }
+}
+
+impl Target {
+    pub fn matches(&self, addr: &IpAddr) -> bool {
+        match self {
+            Target::Any => true,
+            Target::Network(network) => network.contains(addr),
+            Target::Range(start, end) => {
+                addr.bit_len() == start.bit_len()
+                    && addr.bit_len() == end.bit_len()
+                    && start.to_bits() <= addr.to_bits()
+                    && addr.to_bits() <= end.to_bits()
+            }
+        }
+    }
+
+    // Number of matching addresses, saturating at `u128::MAX`. Fewer addresses is more
+    // specific; for networks this is the same as a longer prefix.
+    fn size(&self) -> u128 {
+        match self {
+            Target::Any => u128::MAX,
+            Target::Network(network) => 1u128
+                .checked_shl(u32::from(
+                    network.address().bit_len() - network.prefix_len(),
+                ))
+                .unwrap_or(u128::MAX),
+            Target::Range(start, end) => end
+                .to_bits()
+                .saturating_sub(start.to_bits())
+                .saturating_add(1),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Allow => write!(f, "allow"),
+            Action::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Target::Any => write!(f, "any"),
+            Target::Network(network) => write!(f, "{}", network),
+            Target::Range(start, end) => write!(f, "{}-{}", start, end),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.action, self.target)
+    }
+}
+
+// The outcome of checking an address, with the rule that decided it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision<'a> {
+    pub addr: IpAddr,
+    pub action: Action,
+    // Position in the list (starting at 1) and the rule, `None` when the default was used
+    pub rule: Option<(usize, &'a Rule)>,
+}
+
+impl<'a> Decision<'a> {
+    pub fn is_allowed(&self) -> bool {
+        self.action == Action::Allow
+    }
+
+    pub fn explain(&self) -> String {
+        match self.rule {
+            Some((number, rule)) => format!(
+                "{} {}: matched rule {} ({})",
+                self.action, self.addr, number, rule
+            ),
+            None => format!(
+                "{} {}: no rule matched, using the default",
+                self.action, self.addr
+            ),
+        }
+    }
+}
+
+pub struct Acl {
+    rules: Vec<Rule>,
+    mode: Mode,
+    default: Action,
+}
+
+impl Acl {
+    // An empty list that denies everything
+    pub fn new(mode: Mode) -> Acl {
+        Acl {
+            rules: Vec::new(),
+            mode,
+            default: Action::Deny,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Acl, AclError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn set_default(&mut self, action: Action) {
+        self.default = action;
+    }
+
+    pub fn push(&mut self, action: Action, target: Target) {
+        self.rules.push(Rule { action, target });
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn evaluate(&self, addr: &IpAddr) -> Decision<'_> {
+        let mut matching = self
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.target.matches(addr));
+
+        let found = match self.mode {
+            Mode::FirstMatch => matching.next(),
+            // `min_by_key` keeps the first of equal elements
+            Mode::LongestPrefix => matching.min_by_key(|(_, rule)| rule.target.size()),
+        };
+
+        match found {
+            Some((index, rule)) => Decision {
+                addr: *addr,
+                action: rule.action,
+                rule: Some((index + 1, rule)),
+            },
+            None => Decision {
+                addr: *addr,
+                action: self.default,
+                rule: None,
+            },
+        }
+    }
+
+    pub fn is_allowed(&self, addr: &IpAddr) -> bool {
+        self.evaluate(addr).is_allowed()
+    }
+}
+
+impl FromStr for Acl {
+    type Err = AclError;
+
+    fn from_str(s: &str) -> Result<Acl, AclError> {
+        let mut acl = Acl::new(Mode::FirstMatch);
+
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            parse_line(&mut acl, line).map_err(|message| AclError::Parse {
+                line: index + 1,
+                message,
+            })?;
+        }
+
+        Ok(acl)
+    }
+}
+
+fn parse_line(acl: &mut Acl, line: &str) -> Result<(), String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (keyword, value) = match words.as_slice() {
+        [keyword, value] => (*keyword, *value),
+        _ => return Err(format!("expected `<keyword> <value>`, got {:?}", line)),
+    };
+
+    match keyword {
+        "allow" => acl.push(Action::Allow, parse_target(value)?),
+        "deny" => acl.push(Action::Deny, parse_target(value)?),
+        "default" => acl.default = parse_action(value)?,
+        "mode" => {
+            acl.mode = match value {
+                "first-match" => Mode::FirstMatch,
+                "longest-prefix" => Mode::LongestPrefix,
+                _ => return Err(format!("unknown mode {:?}", value)),
+            }
+        }
+        _ => return Err(format!("unknown keyword {:?}", keyword)),
+    }
+
+    Ok(())
+}
+
+fn parse_action(s: &str) -> Result<Action, String> {
+    match s {
+        "allow" => Ok(Action::Allow),
+        "deny" => Ok(Action::Deny),
+        _ => Err(format!("unknown action {:?}", s)),
+    }
+}
+
+fn parse_target(s: &str) -> Result<Target, String> {
+    if s == "any" {
+        return Ok(Target::Any);
+    }
+
+    if let Some(i) = s.find('-') {
+        let start: IpAddr = s[..i].parse().map_err(|e| format!("{}", e))?;
+        let end: IpAddr = s[i + 1..].parse().map_err(|e| format!("{}", e))?;
+
+        if start.bit_len() != end.bit_len() {
+            return Err(format!("range {:?} mixes IPv4 and IPv6", s));
+        }
+        if start.to_bits() > end.to_bits() {
+            return Err(format!("range {:?} ends before it starts", s));
+        }
+        return Ok(Target::Range(start, end));
+    }
+
+    s.parse().map(Target::Network).map_err(|e| format!("{}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    const RULES: &str = "
+        # office network
+        allow 10.0.0.0/8
+        deny 10.0.13.37
+        allow 192.168.1.10-192.168.1.20
+        deny 2001:db8::/32
+        allow 2001:db8:1::/48
+    ";
+
+    #[test]
+    fn first_match_uses_the_earliest_rule() {
+        let acl: Acl = RULES.parse().unwrap();
+
+        let decision = acl.evaluate(&ip("10.0.13.37"));
+        assert!(decision.is_allowed());
+        assert_eq!(decision.rule.unwrap().0, 1);
+
+        assert!(acl.is_allowed(&ip("192.168.1.15")));
+        assert!(!acl.is_allowed(&ip("192.168.1.21")));
+        assert!(!acl.is_allowed(&ip("2001:db8:1::1")));
+    }
+
+    #[test]
+    fn longest_prefix_uses_the_most_specific_rule() {
+        let acl: Acl = format!("mode longest-prefix\n{}", RULES).parse().unwrap();
+
+        assert!(!acl.is_allowed(&ip("10.0.13.37")));
+        assert!(acl.is_allowed(&ip("10.0.13.38")));
+        assert!(acl.is_allowed(&ip("2001:db8:1::1")));
+        assert!(!acl.is_allowed(&ip("2001:db8:2::1")));
+    }
+
+    #[test]
+    fn decisions_explain_which_rule_matched() {
+        let mut acl = Acl::new(Mode::FirstMatch);
+        acl.push(Action::Deny, Target::Network("10.0.0.0/8".parse().unwrap()));
+        acl.push(Action::Allow, Target::Any);
+
+        assert_eq!(
+            acl.evaluate(&ip("10.1.1.1")).explain(),
+            "deny 10.1.1.1: matched rule 1 (deny 10.0.0.0/8)"
+        );
+        assert_eq!(
+            acl.evaluate(&ip("::1")).explain(),
+            "allow ::1: matched rule 2 (allow any)"
+        );
+
+        let mut empty = Acl::new(Mode::LongestPrefix);
+        empty.set_default(Action::Allow);
+        assert_eq!(
+            empty.evaluate(&ip("1.2.3.4")).explain(),
+            "allow 1.2.3.4: no rule matched, using the default"
+        );
+    }
+
+    #[test]
+    fn rules_are_loaded_from_a_file() {
+        let path = env::temp_dir().join(format!("acl_{}.txt", std::process::id()));
+        fs::write(&path, "default allow\ndeny 203.0.113.0/24\n").unwrap();
+
+        let acl = Acl::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(acl.rules().len(), 1);
+        assert!(!acl.is_allowed(&ip("203.0.113.9")));
+        assert!(acl.is_allowed(&ip("198.51.100.1")));
+    }
+
+    #[test]
+    fn invalid_rules_report_their_line() {
+        let cases = [
+            (
+                "allow 10.0.0.0/8\npermit 10.0.0.1",
+                2,
+                "unknown keyword \"permit\"",
+            ),
+            (
+                "\n\nallow 10.0.0.0/40",
+                3,
+                "prefix length /40 is not between /0 and /32",
+            ),
+            (
+                "deny 10.0.0.9-10.0.0.1",
+                1,
+                "range \"10.0.0.9-10.0.0.1\" ends before it starts",
+            ),
+            (
+                "deny 10.0.0.1-::1",
+                1,
+                "range \"10.0.0.1-::1\" mixes IPv4 and IPv6",
+            ),
+            ("mode fastest", 1, "unknown mode \"fastest\""),
+            ("allow", 1, "expected `<keyword> <value>`, got \"allow\""),
+        ];
+
+        for (text, expected_line, expected_message) in cases.iter() {
+            match text.parse::<Acl>() {
+                Err(AclError::Parse { line, message }) => {
+                    assert_eq!(line, *expected_line);
+                    assert_eq!(message, *expected_message);
+                }
+                _ => panic!("expected a parse error for {:?}", text),
+            }
+        }
+    }
+}
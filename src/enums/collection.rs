@@ -0,0 +1,169 @@
+// Keeps track of a collection of state quarters: which ones a collector has (and how many of
+// each), which ones are still missing, and how far along the collection is.
+
+use std::collections::HashMap;
+
+use super::money::{Coin, UsState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Series {
+    // The quarters of the 50 states
+    States,
+    // The states plus the District of Columbia and the territories
+    StatesAndTerritories,
+}
+
+impl Series {
+    pub fn includes(&self, state: UsState) -> bool {
+        match self {
+            Series::States => !state.is_territory(),
+            Series::StatesAndTerritories => true,
+        }
+    }
+
+    pub fn states(&self) -> Vec<UsState> {
+        UsState::ALL
+            .iter()
+            .copied()
+            .filter(|state| self.includes(*state))
+            .collect()
+    }
+}
+
+pub struct QuarterCollection {
+    series: Series,
+    counts: HashMap<UsState, u32>,
+}
+
+impl QuarterCollection {
+    pub fn new(series: Series) -> QuarterCollection {
+        QuarterCollection {
+            series,
+            counts: HashMap::new(),
+        }
+    }
+
+    // Returns how many quarters of that state are in the collection now
+    pub fn add(&mut self, state: UsState) -> u32 {
+        let count = self.counts.entry(state).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    // Only quarters can be added, returns false for any other coin
+    pub fn add_coin(&mut self, coin: Coin) -> bool {
+        match coin {
+            Coin::Quarter(state) => {
+                self.add(state);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Takes one quarter of that state out, e.g. to trade it. False if there was none.
+    pub fn remove(&mut self, state: UsState) -> bool {
+        match self.counts.get_mut(&state) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                true
+            }
+            Some(_) => {
+                self.counts.remove(&state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn count(&self, state: UsState) -> u32 {
+        self.counts.get(&state).copied().unwrap_or(0)
+    }
+
+    pub fn owns(&self, state: UsState) -> bool {
+        self.count(state) > 0
+    }
+
+    // Every state in the collection, in the order of `UsState::ALL`
+    pub fn owned(&self) -> Vec<UsState> {
+        UsState::ALL
+            .iter()
+            .copied()
+            .filter(|state| self.owns(*state))
+            .collect()
+    }
+
+    // States of the series that are not in the collection yet
+    pub fn missing(&self) -> Vec<UsState> {
+        self.series
+            .states()
+            .into_iter()
+            .filter(|state| !self.owns(*state))
+            .collect()
+    }
+
+    // States with more than one quarter, good for trading
+    pub fn duplicates(&self) -> Vec<(UsState, u32)> {
+        UsState::ALL
+            .iter()
+            .filter(|state| self.count(**state) > 1)
+            .map(|state| (*state, self.count(*state) - 1))
+            .collect()
+    }
+
+    // Share of the series that is owned, between 0 and 100. Quarters from outside the series
+    // don't count.
+    pub fn completion(&self) -> f64 {
+        let states = self.series.states();
+        let owned = states.iter().filter(|state| self.owns(**state)).count();
+
+        100.0 * owned as f64 / states.len() as f64
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_owned_and_missing_quarters() {
+        let mut collection = QuarterCollection::new(Series::States);
+
+        assert!(collection.add_coin(Coin::Quarter(UsState::Ohio)));
+        assert!(!collection.add_coin(Coin::Dime));
+        assert_eq!(collection.add(UsState::Ohio), 2);
+        collection.add(UsState::Guam);
+
+        assert_eq!(collection.owned(), vec![UsState::Ohio, UsState::Guam]);
+        assert_eq!(collection.duplicates(), vec![(UsState::Ohio, 1)]);
+        assert_eq!(collection.missing().len(), 49);
+        assert!(!collection.missing().contains(&UsState::Ohio));
+        assert!((collection.completion() - 2.0).abs() < 1e-9);
+
+        assert!(collection.remove(UsState::Ohio));
+        assert!(collection.remove(UsState::Ohio));
+        assert!(!collection.remove(UsState::Ohio));
+        assert_eq!(collection.count(UsState::Ohio), 0);
+    }
+
+    #[test]
+    fn completion_depends_on_the_series() {
+        let mut states = QuarterCollection::new(Series::States);
+        let mut everything = QuarterCollection::new(Series::StatesAndTerritories);
+
+        for state in Series::States.states() {
+            states.add(state);
+            everything.add(state);
+        }
+
+        assert!(states.is_complete());
+        assert_eq!(states.completion(), 100.0);
+        assert!(!everything.is_complete());
+        assert_eq!(everything.missing().len(), 6);
+        assert!((everything.completion() - 100.0 * 50.0 / 56.0).abs() < 1e-9);
+    }
+}
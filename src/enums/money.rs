@@ -0,0 +1,829 @@
+// US money. `Coin` is the classic enum example: most variants carry no data, but a quarter
+// remembers the state on its back. `Money` is an amount in cents whose arithmetic never
+// silently overflows, and `make_change` finds the fewest pieces for an amount.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UsState {
+    Alabama,
+    Alaska,
+    Arizona,
+    Arkansas,
+    California,
+    Colorado,
+    Connecticut,
+    Delaware,
+    Florida,
+    Georgia,
+    Hawaii,
+    Idaho,
+    Illinois,
+    Indiana,
+    Iowa,
+    Kansas,
+    Kentucky,
+    Louisiana,
+    Maine,
+    Maryland,
+    Massachusetts,
+    Michigan,
+    Minnesota,
+    Mississippi,
+    Missouri,
+    Montana,
+    Nebraska,
+    Nevada,
+    NewHampshire,
+    NewJersey,
+    NewMexico,
+    NewYork,
+    NorthCarolina,
+    NorthDakota,
+    Ohio,
+    Oklahoma,
+    Oregon,
+    Pennsylvania,
+    RhodeIsland,
+    SouthCarolina,
+    SouthDakota,
+    Tennessee,
+    Texas,
+    Utah,
+    Vermont,
+    Virginia,
+    Washington,
+    WestVirginia,
+    Wisconsin,
+    Wyoming,
+    // The District of Columbia and the territories, their quarters came out in 2009
+    DistrictOfColumbia,
+    PuertoRico,
+    Guam,
+    AmericanSamoa,
+    UsVirginIslands,
+    NorthernMarianaIslands,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseStateError(pub String);
+
+impl fmt::Display for ParseStateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown state or territory {:?}", self.0)
+    }
+}
+
+impl UsState {
+    // The 50 states in alphabetical order, followed by the territories
+    pub const ALL: [UsState; 56] = [
+        UsState::Alabama,
+        UsState::Alaska,
+        UsState::Arizona,
+        UsState::Arkansas,
+        UsState::California,
+        UsState::Colorado,
+        UsState::Connecticut,
+        UsState::Delaware,
+        UsState::Florida,
+        UsState::Georgia,
+        UsState::Hawaii,
+        UsState::Idaho,
+        UsState::Illinois,
+        UsState::Indiana,
+        UsState::Iowa,
+        UsState::Kansas,
+        UsState::Kentucky,
+        UsState::Louisiana,
+        UsState::Maine,
+        UsState::Maryland,
+        UsState::Massachusetts,
+        UsState::Michigan,
+        UsState::Minnesota,
+        UsState::Mississippi,
+        UsState::Missouri,
+        UsState::Montana,
+        UsState::Nebraska,
+        UsState::Nevada,
+        UsState::NewHampshire,
+        UsState::NewJersey,
+        UsState::NewMexico,
+        UsState::NewYork,
+        UsState::NorthCarolina,
+        UsState::NorthDakota,
+        UsState::Ohio,
+        UsState::Oklahoma,
+        UsState::Oregon,
+        UsState::Pennsylvania,
+        UsState::RhodeIsland,
+        UsState::SouthCarolina,
+        UsState::SouthDakota,
+        UsState::Tennessee,
+        UsState::Texas,
+        UsState::Utah,
+        UsState::Vermont,
+        UsState::Virginia,
+        UsState::Washington,
+        UsState::WestVirginia,
+        UsState::Wisconsin,
+        UsState::Wyoming,
+        UsState::DistrictOfColumbia,
+        UsState::PuertoRico,
+        UsState::Guam,
+        UsState::AmericanSamoa,
+        UsState::UsVirginIslands,
+        UsState::NorthernMarianaIslands,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UsState::Alabama => "Alabama",
+            UsState::Alaska => "Alaska",
+            UsState::Arizona => "Arizona",
+            UsState::Arkansas => "Arkansas",
+            UsState::California => "California",
+            UsState::Colorado => "Colorado",
+            UsState::Connecticut => "Connecticut",
+            UsState::Delaware => "Delaware",
+            UsState::Florida => "Florida",
+            UsState::Georgia => "Georgia",
+            UsState::Hawaii => "Hawaii",
+            UsState::Idaho => "Idaho",
+            UsState::Illinois => "Illinois",
+            UsState::Indiana => "Indiana",
+            UsState::Iowa => "Iowa",
+            UsState::Kansas => "Kansas",
+            UsState::Kentucky => "Kentucky",
+            UsState::Louisiana => "Louisiana",
+            UsState::Maine => "Maine",
+            UsState::Maryland => "Maryland",
+            UsState::Massachusetts => "Massachusetts",
+            UsState::Michigan => "Michigan",
+            UsState::Minnesota => "Minnesota",
+            UsState::Mississippi => "Mississippi",
+            UsState::Missouri => "Missouri",
+            UsState::Montana => "Montana",
+            UsState::Nebraska => "Nebraska",
+            UsState::Nevada => "Nevada",
+            UsState::NewHampshire => "New Hampshire",
+            UsState::NewJersey => "New Jersey",
+            UsState::NewMexico => "New Mexico",
+            UsState::NewYork => "New York",
+            UsState::NorthCarolina => "North Carolina",
+            UsState::NorthDakota => "North Dakota",
+            UsState::Ohio => "Ohio",
+            UsState::Oklahoma => "Oklahoma",
+            UsState::Oregon => "Oregon",
+            UsState::Pennsylvania => "Pennsylvania",
+            UsState::RhodeIsland => "Rhode Island",
+            UsState::SouthCarolina => "South Carolina",
+            UsState::SouthDakota => "South Dakota",
+            UsState::Tennessee => "Tennessee",
+            UsState::Texas => "Texas",
+            UsState::Utah => "Utah",
+            UsState::Vermont => "Vermont",
+            UsState::Virginia => "Virginia",
+            UsState::Washington => "Washington",
+            UsState::WestVirginia => "West Virginia",
+            UsState::Wisconsin => "Wisconsin",
+            UsState::Wyoming => "Wyoming",
+            UsState::DistrictOfColumbia => "District of Columbia",
+            UsState::PuertoRico => "Puerto Rico",
+            UsState::Guam => "Guam",
+            UsState::AmericanSamoa => "American Samoa",
+            UsState::UsVirginIslands => "U.S. Virgin Islands",
+            UsState::NorthernMarianaIslands => "Northern Mariana Islands",
+        }
+    }
+
+    // The two letter postal abbreviation
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            UsState::Alabama => "AL",
+            UsState::Alaska => "AK",
+            UsState::Arizona => "AZ",
+            UsState::Arkansas => "AR",
+            UsState::California => "CA",
+            UsState::Colorado => "CO",
+            UsState::Connecticut => "CT",
+            UsState::Delaware => "DE",
+            UsState::Florida => "FL",
+            UsState::Georgia => "GA",
+            UsState::Hawaii => "HI",
+            UsState::Idaho => "ID",
+            UsState::Illinois => "IL",
+            UsState::Indiana => "IN",
+            UsState::Iowa => "IA",
+            UsState::Kansas => "KS",
+            UsState::Kentucky => "KY",
+            UsState::Louisiana => "LA",
+            UsState::Maine => "ME",
+            UsState::Maryland => "MD",
+            UsState::Massachusetts => "MA",
+            UsState::Michigan => "MI",
+            UsState::Minnesota => "MN",
+            UsState::Mississippi => "MS",
+            UsState::Missouri => "MO",
+            UsState::Montana => "MT",
+            UsState::Nebraska => "NE",
+            UsState::Nevada => "NV",
+            UsState::NewHampshire => "NH",
+            UsState::NewJersey => "NJ",
+            UsState::NewMexico => "NM",
+            UsState::NewYork => "NY",
+            UsState::NorthCarolina => "NC",
+            UsState::NorthDakota => "ND",
+            UsState::Ohio => "OH",
+            UsState::Oklahoma => "OK",
+            UsState::Oregon => "OR",
+            UsState::Pennsylvania => "PA",
+            UsState::RhodeIsland => "RI",
+            UsState::SouthCarolina => "SC",
+            UsState::SouthDakota => "SD",
+            UsState::Tennessee => "TN",
+            UsState::Texas => "TX",
+            UsState::Utah => "UT",
+            UsState::Vermont => "VT",
+            UsState::Virginia => "VA",
+            UsState::Washington => "WA",
+            UsState::WestVirginia => "WV",
+            UsState::Wisconsin => "WI",
+            UsState::Wyoming => "WY",
+            UsState::DistrictOfColumbia => "DC",
+            UsState::PuertoRico => "PR",
+            UsState::Guam => "GU",
+            UsState::AmericanSamoa => "AS",
+            UsState::UsVirginIslands => "VI",
+            UsState::NorthernMarianaIslands => "MP",
+        }
+    }
+
+    // The District of Columbia counts as a territory here
+    pub fn is_territory(&self) -> bool {
+        matches!(
+            self,
+            UsState::DistrictOfColumbia
+                | UsState::PuertoRico
+                | UsState::Guam
+                | UsState::AmericanSamoa
+                | UsState::UsVirginIslands
+                | UsState::NorthernMarianaIslands
+        )
+    }
+}
+
+impl fmt::Display for UsState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for UsState {
+    type Err = ParseStateError;
+
+    // The name or the abbreviation, ignoring case, extra spaces and dots
+    fn from_str(s: &str) -> Result<UsState, ParseStateError> {
+        let normalize = |s: &str| {
+            s.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .replace('.', "")
+                .to_lowercase()
+        };
+        let wanted = normalize(s);
+
+        UsState::ALL
+            .iter()
+            .find(|state| {
+                normalize(state.name()) == wanted || state.abbreviation().to_lowercase() == wanted
+            })
+            .copied()
+            .ok_or_else(|| ParseStateError(String::from(s)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Coin {
+    Penny,
+    Nickel,
+    Dime,
+    Quarter(UsState),
+    HalfDollar,
+    Dollar,
+}
+
+impl Coin {
+    pub fn denomination(&self) -> Denomination {
+        match self {
+            Coin::Penny => Denomination::Penny,
+            Coin::Nickel => Denomination::Nickel,
+            Coin::Dime => Denomination::Dime,
+            Coin::Quarter(_) => Denomination::Quarter,
+            Coin::HalfDollar => Denomination::HalfDollar,
+            Coin::Dollar => Denomination::DollarCoin,
+        }
+    }
+
+    pub fn value(&self) -> Money {
+        self.denomination().value()
+    }
+}
+
+// Every coin and note in circulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Denomination {
+    Penny,
+    Nickel,
+    Dime,
+    Quarter,
+    HalfDollar,
+    DollarCoin,
+    OneDollarNote,
+    TwoDollarNote,
+    FiveDollarNote,
+    TenDollarNote,
+    TwentyDollarNote,
+    FiftyDollarNote,
+    HundredDollarNote,
+}
+
+impl Denomination {
+    pub const ALL: [Denomination; 13] = [
+        Denomination::Penny,
+        Denomination::Nickel,
+        Denomination::Dime,
+        Denomination::Quarter,
+        Denomination::HalfDollar,
+        Denomination::DollarCoin,
+        Denomination::OneDollarNote,
+        Denomination::TwoDollarNote,
+        Denomination::FiveDollarNote,
+        Denomination::TenDollarNote,
+        Denomination::TwentyDollarNote,
+        Denomination::FiftyDollarNote,
+        Denomination::HundredDollarNote,
+    ];
+
+    pub fn value(&self) -> Money {
+        let cents = match self {
+            Denomination::Penny => 1,
+            Denomination::Nickel => 5,
+            Denomination::Dime => 10,
+            Denomination::Quarter => 25,
+            Denomination::HalfDollar => 50,
+            Denomination::DollarCoin | Denomination::OneDollarNote => 100,
+            Denomination::TwoDollarNote => 200,
+            Denomination::FiveDollarNote => 500,
+            Denomination::TenDollarNote => 1_000,
+            Denomination::TwentyDollarNote => 2_000,
+            Denomination::FiftyDollarNote => 5_000,
+            Denomination::HundredDollarNote => 10_000,
+        };
+        Money::from_cents(cents)
+    }
+
+    pub fn is_coin(&self) -> bool {
+        self.value() < Money::from_cents(100) || *self == Denomination::DollarCoin
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Money {
+    cents: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseMoneyError {
+    Invalid(String),
+    TooLarge(String),
+}
+
+impl fmt::Display for ParseMoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseMoneyError::Invalid(s) => write!(f, "invalid amount of money {:?}", s),
+            ParseMoneyError::TooLarge(s) => write!(f, "amount of money {:?} is too large", s),
+        }
+    }
+}
+
+impl Money {
+    pub fn from_cents(cents: u64) -> Money {
+        Money { cents }
+    }
+
+    // `None` on overflow or when `cents` is 100 or more
+    pub fn from_dollars(dollars: u64, cents: u64) -> Option<Money> {
+        if cents >= 100 {
+            return None;
+        }
+        dollars
+            .checked_mul(100)
+            .and_then(|total| total.checked_add(cents))
+            .map(Money::from_cents)
+    }
+
+    pub fn cents(&self) -> u64 {
+        self.cents
+    }
+
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.cents.checked_add(other.cents).map(Money::from_cents)
+    }
+
+    // `None` when the result would be negative
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.cents.checked_sub(other.cents).map(Money::from_cents)
+    }
+
+    pub fn checked_mul(self, factor: u64) -> Option<Money> {
+        self.cents.checked_mul(factor).map(Money::from_cents)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "${}.{:02}", self.cents / 100, self.cents % 100)
+    }
+}
+
+impl FromStr for Money {
+    type Err = ParseMoneyError;
+
+    // `$12.34`, `12.5` or `12`, the dollar sign is optional and there are at most two digits
+    // after the point
+    fn from_str(s: &str) -> Result<Money, ParseMoneyError> {
+        let invalid = || ParseMoneyError::Invalid(String::from(s));
+        let amount = s.strip_prefix('$').unwrap_or(s);
+
+        let (dollars, cents) = match amount.find('.') {
+            Some(i) => (&amount[..i], &amount[i + 1..]),
+            None => (amount, ""),
+        };
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if dollars.is_empty() || !all_digits(dollars) || !all_digits(cents) || cents.len() > 2 {
+            return Err(invalid());
+        }
+        if amount.ends_with('.') {
+            return Err(invalid());
+        }
+
+        let dollars = dollars
+            .parse()
+            .map_err(|_| ParseMoneyError::TooLarge(String::from(s)))?;
+        let cents = match cents.len() {
+            0 => 0,
+            1 => cents.parse::<u64>().map_err(|_| invalid())? * 10,
+            _ => cents.parse().map_err(|_| invalid())?,
+        };
+
+        Money::from_dollars(dollars, cents)
+            .ok_or_else(|| ParseMoneyError::TooLarge(String::from(s)))
+    }
+}
+
+// The fewest pieces from `denominations` adding up to `amount`, as each denomination used with
+// its number of pieces, largest first. Each denomination can be used any number of times;
+// `None` when the amount cannot be paid.
+//
+// The greedy approach (always take the largest piece that fits) is optimal for US money but
+// not for every set, with pieces of 1, 3 and 4 it pays 6 as 4 + 1 + 1 instead of 3 + 3. So
+// the set is checked first and dynamic programming is used when greedy could go wrong. That
+// takes time and memory proportional to the amount, so it is only done for amounts up to
+// `MAX_TABLE_AMOUNT` cents and gives `None` above that.
+pub fn make_change(amount: Money, denominations: &[Money]) -> Option<Vec<(Money, u64)>> {
+    let mut values: Vec<u64> = denominations
+        .iter()
+        .map(|d| d.cents())
+        .filter(|cents| *cents > 0)
+        .collect();
+    values.sort_unstable_by(|a, b| b.cmp(a));
+    values.dedup();
+
+    let counts = if is_canonical(&values) {
+        greedy(amount.cents(), &values)
+    } else {
+        fewest_pieces(amount.cents(), &values)
+    }?;
+    Some(
+        counts
+            .into_iter()
+            .map(|(value, count)| (Money::from_cents(value), count))
+            .collect(),
+    )
+}
+
+// Change paid out in the given denominations, `None` when the amount cannot be paid
+pub fn make_change_in(
+    amount: Money,
+    available: &[Denomination],
+) -> Option<Vec<(Denomination, u64)>> {
+    let values: Vec<Money> = available.iter().map(|d| d.value()).collect();
+    let counts = make_change(amount, &values)?;
+
+    // Coins and notes of the same value are interchangeable, the first listed one is used
+    Some(
+        counts
+            .iter()
+            .map(|(value, count)| {
+                let denomination = available.iter().find(|d| d.value() == *value).unwrap();
+                (*denomination, *count)
+            })
+            .collect(),
+    )
+}
+
+// `denominations` sorted from largest to smallest
+fn greedy(mut amount: u64, denominations: &[u64]) -> Option<Vec<(u64, u64)>> {
+    let mut counts = Vec::new();
+
+    for &value in denominations {
+        let count = amount / value;
+        if count > 0 {
+            amount -= count * value;
+            counts.push((value, count));
+        }
+    }
+
+    if amount == 0 {
+        Some(counts)
+    } else {
+        None
+    }
+}
+
+// Largest amount in cents that change is worked out for with dynamic programming
+pub const MAX_TABLE_AMOUNT: u64 = 1_000_000;
+
+// Smallest number of pieces for every amount up to `amount`, with the last piece used.
+// `None` when the amount is above `MAX_TABLE_AMOUNT`.
+fn fewest_pieces_table(amount: u64, denominations: &[u64]) -> Option<Vec<Option<(u64, u64)>>> {
+    if amount > MAX_TABLE_AMOUNT {
+        return None;
+    }
+
+    let mut table = vec![None; amount as usize + 1];
+    table[0] = Some((0, 0));
+
+    for current in 1..=amount {
+        table[current as usize] = denominations
+            .iter()
+            .filter(|value| **value <= current)
+            .filter_map(|value| {
+                table[(current - value) as usize].map(|(count, _)| (count + 1, *value))
+            })
+            .min_by_key(|(count, _)| *count);
+    }
+
+    Some(table)
+}
+
+fn fewest_pieces(amount: u64, denominations: &[u64]) -> Option<Vec<(u64, u64)>> {
+    let table = fewest_pieces_table(amount, denominations)?;
+    table[amount as usize]?;
+
+    let mut counts: Vec<(u64, u64)> = denominations.iter().map(|value| (*value, 0)).collect();
+    let mut remaining = amount;
+    while remaining > 0 {
+        let (_, value) = table[remaining as usize].unwrap();
+        counts.iter_mut().find(|(v, _)| *v == value).unwrap().1 += 1;
+        remaining -= value;
+    }
+
+    counts.retain(|(_, count)| *count > 0);
+    Some(counts)
+}
+
+// Whether greedy is optimal for every amount. With a piece of 1, the smallest amount where it
+// fails is below the sum of the two largest pieces (Kozen and Zaks), so checking up to there
+// is enough. When that bound is too large to check the set is treated as not canonical.
+fn is_canonical(denominations: &[u64]) -> bool {
+    if denominations.last() != Some(&1) {
+        return false;
+    }
+    if denominations.len() < 3 {
+        return true;
+    }
+
+    let table = match denominations[0].checked_add(denominations[1]) {
+        Some(bound) => match fewest_pieces_table(bound, denominations) {
+            Some(table) => table,
+            None => return false,
+        },
+        None => return false,
+    };
+    let bound = table.len() as u64 - 1;
+
+    (1..bound).all(|amount| {
+        let greedy_count = greedy(amount, denominations)
+            .map(|counts| counts.iter().map(|(_, count)| count).sum::<u64>());
+        greedy_count == table[amount as usize].map(|(count, _)| count)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cents(values: &[u64]) -> Vec<Money> {
+        values.iter().map(|c| Money::from_cents(*c)).collect()
+    }
+
+    #[test]
+    fn coins_know_their_value() {
+        assert_eq!(Coin::Penny.value(), Money::from_cents(1));
+        assert_eq!(
+            Coin::Quarter(UsState::Alaska).value(),
+            Money::from_cents(25)
+        );
+        assert_eq!(Coin::Dollar.denomination(), Denomination::DollarCoin);
+
+        let purse = [
+            Coin::Penny,
+            Coin::Nickel,
+            Coin::Dime,
+            Coin::Quarter(UsState::Alabama),
+            Coin::HalfDollar,
+            Coin::Dollar,
+        ];
+        let total: u64 = purse.iter().map(|coin| coin.value().cents()).sum();
+        assert_eq!(total, 191);
+        assert!(Denomination::DollarCoin.is_coin());
+        assert!(!Denomination::OneDollarNote.is_coin());
+        assert!(Denomination::HalfDollar.is_coin());
+    }
+
+    #[test]
+    fn states_are_parsed_from_names_and_abbreviations() {
+        assert_eq!("Alaska".parse(), Ok(UsState::Alaska));
+        assert_eq!("  new   YORK ".parse(), Ok(UsState::NewYork));
+        assert_eq!("wv".parse(), Ok(UsState::WestVirginia));
+        assert_eq!("US Virgin Islands".parse(), Ok(UsState::UsVirginIslands));
+        assert_eq!(
+            "Atlantis".parse::<UsState>(),
+            Err(ParseStateError(String::from("Atlantis")))
+        );
+
+        assert_eq!(
+            UsState::ALL.iter().filter(|s| !s.is_territory()).count(),
+            50
+        );
+        for state in UsState::ALL.iter() {
+            assert_eq!(state.to_string().parse(), Ok(*state));
+            assert_eq!(state.abbreviation().parse(), Ok(*state));
+        }
+        assert_eq!(
+            UsState::DistrictOfColumbia.to_string(),
+            "District of Columbia"
+        );
+    }
+
+    #[test]
+    fn money_arithmetic_is_checked() {
+        let price = Money::from_dollars(3, 99).unwrap();
+
+        assert_eq!(
+            price.checked_add(Money::from_cents(1)),
+            Money::from_dollars(4, 0)
+        );
+        assert_eq!(price.checked_mul(3), Some(Money::from_cents(1_197)));
+        assert_eq!(Money::from_cents(1).checked_sub(price), None);
+        assert_eq!(
+            Money::from_cents(u64::MAX).checked_add(Money::from_cents(1)),
+            None
+        );
+        assert_eq!(Money::from_dollars(u64::MAX, 0), None);
+        assert_eq!(Money::from_dollars(1, 100), None);
+    }
+
+    #[test]
+    fn money_is_parsed_and_displayed() {
+        assert_eq!("$12.34".parse(), Ok(Money::from_cents(1_234)));
+        assert_eq!("12.5".parse(), Ok(Money::from_cents(1_250)));
+        assert_eq!("7".parse(), Ok(Money::from_cents(700)));
+        assert_eq!(Money::from_cents(1_205).to_string(), "$12.05");
+        assert_eq!(Money::default().to_string(), "$0.00");
+
+        for s in ["", "$", "1.234", "1.", ".5", "-1", "1,00", "$$1"].iter() {
+            assert_eq!(
+                s.parse::<Money>(),
+                Err(ParseMoneyError::Invalid(String::from(*s)))
+            );
+        }
+        assert!(matches!(
+            "999999999999999999999".parse::<Money>(),
+            Err(ParseMoneyError::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn change_uses_the_fewest_pieces() {
+        let change = make_change_in(Money::from_cents(3_791), &Denomination::ALL).unwrap();
+        assert_eq!(
+            change,
+            vec![
+                (Denomination::TwentyDollarNote, 1),
+                (Denomination::TenDollarNote, 1),
+                (Denomination::FiveDollarNote, 1),
+                (Denomination::TwoDollarNote, 1),
+                (Denomination::HalfDollar, 1),
+                (Denomination::Quarter, 1),
+                (Denomination::Dime, 1),
+                (Denomination::Nickel, 1),
+                (Denomination::Penny, 1),
+            ]
+        );
+
+        // Greedy would pay 30 as 25 + 5 x 1
+        let coins = [
+            Denomination::Penny,
+            Denomination::Dime,
+            Denomination::Quarter,
+        ];
+        assert_eq!(
+            make_change_in(Money::from_cents(30), &coins),
+            Some(vec![(Denomination::Dime, 3)])
+        );
+    }
+
+    #[test]
+    fn change_works_for_any_denominations() {
+        let pairs = |counts: &[(u64, u64)]| -> Vec<(Money, u64)> {
+            counts
+                .iter()
+                .map(|(value, count)| (Money::from_cents(*value), *count))
+                .collect()
+        };
+
+        assert_eq!(
+            make_change(Money::from_cents(6), &cents(&[1, 3, 4])),
+            Some(pairs(&[(3, 2)]))
+        );
+        assert_eq!(
+            make_change(Money::from_cents(10), &cents(&[1, 3, 4])),
+            Some(pairs(&[(4, 1), (3, 2)]))
+        );
+        assert_eq!(
+            make_change(Money::from_cents(9), &cents(&[5, 3])),
+            Some(pairs(&[(3, 3)]))
+        );
+        assert_eq!(make_change(Money::from_cents(7), &cents(&[5, 3])), None);
+        assert_eq!(
+            make_change(Money::from_cents(0), &cents(&[5])),
+            Some(vec![])
+        );
+        assert_eq!(make_change(Money::from_cents(5), &[]), None);
+
+        // Compare against dynamic programming for every small amount
+        let denominations = [1, 5, 10, 25, 50, 100];
+        let pieces = |counts: &[(u64, u64)]| counts.iter().map(|(_, count)| count).sum::<u64>();
+        assert!(is_canonical(&[100, 50, 25, 10, 5, 1]));
+        for amount in 0..500 {
+            let change = greedy(amount, &[100, 50, 25, 10, 5, 1]).unwrap();
+            let expected = fewest_pieces(amount, &[100, 50, 25, 10, 5, 1]).unwrap();
+            assert_eq!(pieces(&change), pieces(&expected));
+            assert_eq!(
+                make_change(Money::from_cents(amount), &cents(&denominations)),
+                Some(pairs(&change))
+            );
+        }
+    }
+
+    #[test]
+    fn change_for_huge_amounts_does_not_overflow() {
+        let amount = Money::from_cents(u64::MAX);
+        assert_eq!(make_change(amount, &cents(&[3, 4])), None);
+        assert_eq!(
+            make_change(Money::from_cents(MAX_TABLE_AMOUNT + 1), &cents(&[3, 4])),
+            None
+        );
+        assert_eq!(
+            make_change(Money::from_cents(MAX_TABLE_AMOUNT), &cents(&[3, 4])),
+            Some(vec![(Money::from_cents(4), 250_000)])
+        );
+
+        // The bound for the canonical check would overflow
+        assert!(!is_canonical(&[u64::MAX, u64::MAX - 1, 1]));
+        assert_eq!(
+            make_change(Money::from_cents(3), &cents(&[u64::MAX, u64::MAX - 1, 1])),
+            Some(vec![(Money::from_cents(1), 3)])
+        );
+
+        // Greedy counts pieces instead of listing every one of them
+        let trillion = Money::from_dollars(1_000_000_000_000, 37).unwrap();
+        assert_eq!(
+            make_change_in(trillion, &Denomination::ALL),
+            Some(vec![
+                (Denomination::HundredDollarNote, 10_000_000_000),
+                (Denomination::Quarter, 1),
+                (Denomination::Dime, 1),
+                (Denomination::Penny, 2),
+            ])
+        );
+        assert_eq!(
+            make_change(amount, &cents(&[1])),
+            Some(vec![(Money::from_cents(1), u64::MAX)])
+        );
+    }
+}
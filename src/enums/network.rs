@@ -0,0 +1,403 @@
+// A network in CIDR notation: an address plus how many of its leading bits (the prefix) are
+// shared by every address in the network, e.g. `10.0.0.0/8` or `2001:db8::/32`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{IpAddr, ParseIpError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Network {
+    // Always the first address of the network, the host bits are zero
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkError {
+    Address(ParseIpError),
+    InvalidPrefix(String),
+    PrefixOutOfRange { prefix_len: u8, min: u8, max: u8 },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::Address(e) => write!(f, "{}", e),
+            NetworkError::InvalidPrefix(s) => write!(f, "invalid prefix length {:?}", s),
+            NetworkError::PrefixOutOfRange {
+                prefix_len,
+                min,
+                max,
+            } => write!(
+                f,
+                "prefix length /{} is not between /{} and /{}",
+                prefix_len, min, max
+            ),
+        }
+    }
+}
+
+impl From<ParseIpError> for NetworkError {
+    fn from(e: ParseIpError) -> NetworkError {
+        NetworkError::Address(e)
+    }
+}
+
+impl Network {
+    // Host bits set in `addr` are cleared, so `10.1.2.3/8` becomes `10.0.0.0/8`
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Network, NetworkError> {
+        check_prefix(prefix_len, 0, addr.bit_len())?;
+
+        Ok(Network {
+            addr: with_bits(&addr, addr.to_bits() & mask(prefix_len, addr.bit_len())),
+            prefix_len,
+        })
+    }
+
+    // The network containing only `addr`
+    pub fn host(addr: IpAddr) -> Network {
+        Network {
+            addr,
+            prefix_len: addr.bit_len(),
+        }
+    }
+
+    pub fn address(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    pub fn netmask(&self) -> IpAddr {
+        with_bits(&self.addr, mask(self.prefix_len, self.addr.bit_len()))
+    }
+
+    // The last address of the network. IPv6 has no broadcast, but the last address is still
+    // useful as the end of the range.
+    pub fn broadcast(&self) -> IpAddr {
+        with_bits(&self.addr, self.last_bits())
+    }
+
+    // First and last usable host. For IPv4 the network and broadcast addresses are left out,
+    // except in /31 and /32 networks which have no room for them (RFC 3021). All IPv6
+    // addresses are usable.
+    pub fn host_range(&self) -> (IpAddr, IpAddr) {
+        let (first, last) = self.host_bits();
+        (with_bits(&self.addr, first), with_bits(&self.addr, last))
+    }
+
+    // Number of usable hosts, saturating at `u128::MAX` for the whole IPv6 space
+    pub fn host_count(&self) -> u128 {
+        let (first, last) = self.host_bits();
+        (last - first).saturating_add(1)
+    }
+
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        addr.bit_len() == self.addr.bit_len()
+            && addr.to_bits() & mask(self.prefix_len, addr.bit_len()) == self.addr.to_bits()
+    }
+
+    // Whether every address of `other` is also in this network
+    pub fn contains_network(&self, other: &Network) -> bool {
+        other.prefix_len >= self.prefix_len && self.contains(&other.addr)
+    }
+
+    // Splits the network into smaller networks with the longer prefix `prefix_len`
+    pub fn subnets(&self, prefix_len: u8) -> Result<Subnets, NetworkError> {
+        check_prefix(prefix_len, self.prefix_len, self.addr.bit_len())?;
+
+        Ok(Subnets {
+            next: Some(self.addr.to_bits()),
+            last: self.last_bits(),
+            step: 1u128.checked_shl(u32::from(self.addr.bit_len() - prefix_len)),
+            template: self.addr,
+            prefix_len,
+        })
+    }
+
+    // The network with the shorter prefix `prefix_len` that contains this one
+    pub fn supernet(&self, prefix_len: u8) -> Result<Network, NetworkError> {
+        check_prefix(prefix_len, 0, self.prefix_len)?;
+        Network::new(self.addr, prefix_len)
+    }
+
+    pub fn hosts(&self) -> Hosts {
+        let (first, last) = self.host_bits();
+        Hosts {
+            next: Some(first),
+            last,
+            template: self.addr,
+        }
+    }
+
+    fn last_bits(&self) -> u128 {
+        self.addr.to_bits()
+            | !mask(self.prefix_len, self.addr.bit_len()) & all_ones(self.addr.bit_len())
+    }
+
+    fn host_bits(&self) -> (u128, u128) {
+        let (first, last) = (self.addr.to_bits(), self.last_bits());
+
+        if self.addr.is_ipv4() && self.prefix_len < 31 {
+            (first + 1, last - 1)
+        } else {
+            (first, last)
+        }
+    }
+}
+
+fn check_prefix(prefix_len: u8, min: u8, max: u8) -> Result<(), NetworkError> {
+    if prefix_len < min || prefix_len > max {
+        return Err(NetworkError::PrefixOutOfRange {
+            prefix_len,
+            min,
+            max,
+        });
+    }
+    Ok(())
+}
+
+fn all_ones(bit_len: u8) -> u128 {
+    u128::MAX >> (128 - u32::from(bit_len))
+}
+
+// The first `prefix_len` of `bit_len` bits set
+fn mask(prefix_len: u8, bit_len: u8) -> u128 {
+    let host_mask = 1u128
+        .checked_shl(u32::from(bit_len - prefix_len))
+        .map_or(u128::MAX, |bit| bit - 1);
+    all_ones(bit_len) & !host_mask
+}
+
+// An address of the same version as `template`
+fn with_bits(template: &IpAddr, bits: u128) -> IpAddr {
+    match template {
+        IpAddr::V4(..) => IpAddr::from_v4_bits(bits as u32),
+        IpAddr::V6(_) => IpAddr::from_v6_bits(bits),
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl FromStr for Network {
+    type Err = NetworkError;
+
+    // `address/prefix`, an address without a prefix is a single host network
+    fn from_str(s: &str) -> Result<Network, NetworkError> {
+        let (addr, prefix) = match s.find('/') {
+            Some(i) => (&s[..i], Some(&s[i + 1..])),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr.parse()?;
+
+        match prefix {
+            None => Ok(Network::host(addr)),
+            Some(prefix) => {
+                let valid = !prefix.is_empty() && prefix.bytes().all(|b| b.is_ascii_digit());
+                let prefix_len = prefix
+                    .parse()
+                    .ok()
+                    .filter(|_| valid)
+                    .ok_or_else(|| NetworkError::InvalidPrefix(String::from(prefix)))?;
+                Network::new(addr, prefix_len)
+            }
+        }
+    }
+}
+
+pub struct Subnets {
+    next: Option<u128>,
+    last: u128,
+    // `None` when a single subnet spans the whole address space
+    step: Option<u128>,
+    template: IpAddr,
+    prefix_len: u8,
+}
+
+impl Iterator for Subnets {
+    type Item = Network;
+
+    fn next(&mut self) -> Option<Network> {
+        let start = self.next?;
+
+        self.next = self
+            .step
+            .and_then(|step| start.checked_add(step))
+            .filter(|next| *next <= self.last);
+
+        Some(Network {
+            addr: with_bits(&self.template, start),
+            prefix_len: self.prefix_len,
+        })
+    }
+}
+
+pub struct Hosts {
+    next: Option<u128>,
+    last: u128,
+    template: IpAddr,
+}
+
+impl Iterator for Hosts {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        let current = self.next?;
+        self.next = if current < self.last {
+            Some(current + 1)
+        } else {
+            None
+        };
+
+        Some(with_bits(&self.template, current))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(s: &str) -> Network {
+        s.parse().unwrap()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_displays_networks() {
+        assert_eq!(net("10.1.2.3/8").to_string(), "10.0.0.0/8");
+        assert_eq!(net("2001:db8::1/32").to_string(), "2001:db8::/32");
+        assert_eq!(net("192.0.2.7").to_string(), "192.0.2.7/32");
+        assert_eq!(net("0.0.0.0/0").to_string(), "0.0.0.0/0");
+
+        assert_eq!(
+            "10.0.0.0/33".parse::<Network>(),
+            Err(NetworkError::PrefixOutOfRange {
+                prefix_len: 33,
+                min: 0,
+                max: 32
+            })
+        );
+        assert_eq!(
+            "10.0.0.0/+8".parse::<Network>(),
+            Err(NetworkError::InvalidPrefix(String::from("+8")))
+        );
+        assert_eq!(
+            "10.0.0.0/".parse::<Network>(),
+            Err(NetworkError::InvalidPrefix(String::new()))
+        );
+        assert!(matches!(
+            "10.0.0/8".parse::<Network>(),
+            Err(NetworkError::Address(_))
+        ));
+    }
+
+    #[test]
+    fn computes_ipv4_addresses_and_ranges() {
+        let network = net("192.168.1.77/24");
+
+        assert_eq!(network.address(), ip("192.168.1.0"));
+        assert_eq!(network.prefix_len(), 24);
+        assert_eq!(network.netmask(), ip("255.255.255.0"));
+        assert_eq!(network.broadcast(), ip("192.168.1.255"));
+        assert_eq!(
+            network.host_range(),
+            (ip("192.168.1.1"), ip("192.168.1.254"))
+        );
+        assert_eq!(network.host_count(), 254);
+
+        assert_eq!(net("10.0.0.0/31").host_count(), 2);
+        assert_eq!(
+            net("10.0.0.5/32").host_range(),
+            (ip("10.0.0.5"), ip("10.0.0.5"))
+        );
+        assert_eq!(net("0.0.0.0/0").host_count(), (1 << 32) - 2);
+    }
+
+    #[test]
+    fn computes_ipv6_addresses_and_ranges() {
+        let network = net("2001:db8:abcd:12::/64");
+
+        assert_eq!(network.netmask(), ip("ffff:ffff:ffff:ffff::"));
+        assert_eq!(
+            network.broadcast(),
+            ip("2001:db8:abcd:12:ffff:ffff:ffff:ffff")
+        );
+        assert_eq!(network.host_range().0, ip("2001:db8:abcd:12::"));
+        assert_eq!(network.host_count(), 1 << 64);
+        assert_eq!(net("::/0").host_count(), u128::MAX);
+        assert_eq!(
+            net("::/0").broadcast(),
+            ip("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff")
+        );
+    }
+
+    #[test]
+    fn contains_addresses_and_networks() {
+        let network = net("172.16.0.0/12");
+
+        assert!(network.contains(&ip("172.31.255.255")));
+        assert!(!network.contains(&ip("172.32.0.0")));
+        assert!(!network.contains(&ip("::ac10:1")));
+        assert!(network.contains_network(&net("172.20.0.0/16")));
+        assert!(!net("172.20.0.0/16").contains_network(&network));
+        assert!(net("::/0").contains(&ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn splits_and_joins_networks() {
+        let subnets: Vec<String> = net("10.0.0.0/24")
+            .subnets(26)
+            .unwrap()
+            .map(|subnet| subnet.to_string())
+            .collect();
+        assert_eq!(
+            subnets,
+            vec![
+                "10.0.0.0/26",
+                "10.0.0.64/26",
+                "10.0.0.128/26",
+                "10.0.0.192/26"
+            ]
+        );
+
+        assert_eq!(net("10.0.0.0/24").subnets(24).unwrap().count(), 1);
+        assert_eq!(net("::/0").subnets(0).unwrap().count(), 1);
+        assert_eq!(
+            net("::/0").subnets(1).unwrap().last(),
+            Some(net("8000::/1"))
+        );
+        assert!(net("10.0.0.0/24").subnets(23).is_err());
+
+        assert_eq!(
+            net("10.0.0.192/26").supernet(22).unwrap(),
+            net("10.0.0.0/22")
+        );
+        assert_eq!(
+            net("2001:db8:ff::/48").supernet(32).unwrap(),
+            net("2001:db8::/32")
+        );
+        assert!(net("10.0.0.0/24").supernet(25).is_err());
+    }
+
+    #[test]
+    fn iterates_over_hosts() {
+        let hosts: Vec<IpAddr> = net("192.168.0.0/30").hosts().collect();
+        assert_eq!(hosts, vec![ip("192.168.0.1"), ip("192.168.0.2")]);
+
+        let hosts: Vec<IpAddr> = net("2001:db8::/127").hosts().collect();
+        assert_eq!(hosts, vec![ip("2001:db8::"), ip("2001:db8::1")]);
+
+        let last = net("255.255.255.254/31").hosts().last();
+        assert_eq!(last, Some(ip("255.255.255.255")));
+    }
+}
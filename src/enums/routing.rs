@@ -0,0 +1,376 @@
+// A routing table that maps network prefixes to next hops and finds the most specific route for
+// an address (longest prefix match). Routes are kept in a binary trie with path compression
+// (a Patricia tree): every node stores the prefix bits it stands for, so chains of nodes with a
+// single child are skipped. IPv4 and IPv6 routes live in separate tries.
+//
+// Inside the trie addresses are left aligned in a `u128`, the first bit of the prefix is always
+// the most significant bit, whatever the IP version.
+
+use super::network::Network;
+use super::IpAddr;
+
+struct Node<T> {
+    key: u128,
+    len: u8,
+    // Only set for prefixes that have a route, other nodes just join two branches
+    value: Option<T>,
+    children: [Option<Box<Node<T>>>; 2],
+}
+
+impl<T> Node<T> {
+    fn new(key: u128, len: u8, value: Option<T>) -> Box<Node<T>> {
+        Box::new(Node {
+            key,
+            len,
+            value,
+            children: [None, None],
+        })
+    }
+}
+
+pub struct RoutingTable<T> {
+    v4: Option<Box<Node<T>>>,
+    v6: Option<Box<Node<T>>>,
+    len: usize,
+}
+
+impl<T> Default for RoutingTable<T> {
+    fn default() -> RoutingTable<T> {
+        RoutingTable::new()
+    }
+}
+
+impl<T> RoutingTable<T> {
+    pub fn new() -> RoutingTable<T> {
+        RoutingTable {
+            v4: None,
+            v6: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Adds a route, returning the next hop it replaced
+    pub fn insert(&mut self, network: Network, next_hop: T) -> Option<T> {
+        let (key, len) = trie_key(&network);
+        let replaced = insert(self.root_mut(&network.address()), key, len, next_hop);
+
+        if replaced.is_none() {
+            self.len += 1;
+        }
+        replaced
+    }
+
+    // Removes the route for exactly this prefix
+    pub fn withdraw(&mut self, network: &Network) -> Option<T> {
+        let (key, len) = trie_key(network);
+        let removed = withdraw(self.root_mut(&network.address()), key, len);
+
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    // The most specific route containing `addr`
+    pub fn lookup(&self, addr: &IpAddr) -> Option<(Network, &T)> {
+        let key = align(addr);
+        let mut best = None;
+        let mut current = self.root(addr).as_ref();
+
+        while let Some(node) = current {
+            if common_prefix_len(node.key, key) < node.len {
+                break;
+            }
+            if let Some(value) = &node.value {
+                best = Some((node.key, node.len, value));
+            }
+            if node.len == addr.bit_len() {
+                break;
+            }
+            current = node.children[bit_at(key, node.len)].as_ref();
+        }
+
+        best.map(|(key, len, value)| (network_from_key(addr, key, len), value))
+    }
+
+    // All routes, IPv4 before IPv6, ordered by address and then by prefix length
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut stack = Vec::new();
+        if let Some(root) = &self.v6 {
+            stack.push((root.as_ref(), IpAddr::V6([0; 8])));
+        }
+        if let Some(root) = &self.v4 {
+            stack.push((root.as_ref(), IpAddr::V4(0, 0, 0, 0)));
+        }
+
+        Iter { stack }
+    }
+
+    fn root(&self, addr: &IpAddr) -> &Option<Box<Node<T>>> {
+        match addr {
+            IpAddr::V4(..) => &self.v4,
+            IpAddr::V6(_) => &self.v6,
+        }
+    }
+
+    fn root_mut(&mut self, addr: &IpAddr) -> &mut Option<Box<Node<T>>> {
+        match addr {
+            IpAddr::V4(..) => &mut self.v4,
+            IpAddr::V6(_) => &mut self.v6,
+        }
+    }
+}
+
+fn insert<T>(slot: &mut Option<Box<Node<T>>>, key: u128, len: u8, value: T) -> Option<T> {
+    let node = match slot {
+        Some(node) => node,
+        None => {
+            *slot = Some(Node::new(key, len, Some(value)));
+            return None;
+        }
+    };
+
+    let common = common_prefix_len(node.key, key).min(node.len).min(len);
+    if common == node.len {
+        if len == node.len {
+            return node.value.replace(value);
+        }
+        return insert(&mut node.children[bit_at(key, node.len)], key, len, value);
+    }
+
+    // The new prefix leaves this node's path part of the way down, split it there
+    let existing = slot.take().unwrap();
+    let mut split = Node::new(key & prefix_mask(common), common, None);
+    let existing_bit = bit_at(existing.key, common);
+    split.children[existing_bit] = Some(existing);
+
+    if len == common {
+        split.value = Some(value);
+    } else {
+        split.children[bit_at(key, common)] = Some(Node::new(key, len, Some(value)));
+    }
+
+    *slot = Some(split);
+    None
+}
+
+fn withdraw<T>(slot: &mut Option<Box<Node<T>>>, key: u128, len: u8) -> Option<T> {
+    let node = slot.as_mut()?;
+    if len < node.len || common_prefix_len(node.key, key) < node.len {
+        return None;
+    }
+
+    let removed = if len == node.len {
+        node.value.take()
+    } else {
+        withdraw(&mut node.children[bit_at(key, node.len)], key, len)
+    };
+
+    if removed.is_some() {
+        compact(slot);
+    }
+    removed
+}
+
+// Drops nodes without a route that no longer join two branches
+fn compact<T>(slot: &mut Option<Box<Node<T>>>) {
+    let node = match slot {
+        Some(node) if node.value.is_none() => node,
+        _ => return,
+    };
+
+    match &mut node.children {
+        [None, None] => *slot = None,
+        [Some(_), None] => *slot = node.children[0].take(),
+        [None, Some(_)] => *slot = node.children[1].take(),
+        [Some(_), Some(_)] => {}
+    }
+}
+
+pub struct Iter<'a, T> {
+    // Nodes still to visit, with an address of the version of their trie
+    stack: Vec<(&'a Node<T>, IpAddr)>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (Network, &'a T);
+
+    fn next(&mut self) -> Option<(Network, &'a T)> {
+        while let Some((node, version)) = self.stack.pop() {
+            for child in node.children.iter().rev().flatten() {
+                self.stack.push((child, version));
+            }
+
+            if let Some(value) = &node.value {
+                return Some((network_from_key(&version, node.key, node.len), value));
+            }
+        }
+
+        None
+    }
+}
+
+fn align(addr: &IpAddr) -> u128 {
+    addr.to_bits() << (128 - u32::from(addr.bit_len()))
+}
+
+fn trie_key(network: &Network) -> (u128, u8) {
+    (align(&network.address()), network.prefix_len())
+}
+
+// `version` only decides whether an IPv4 or IPv6 network is built
+fn network_from_key(version: &IpAddr, key: u128, len: u8) -> Network {
+    let addr = match version {
+        IpAddr::V4(..) => IpAddr::from_v4_bits((key >> 96) as u32),
+        IpAddr::V6(_) => IpAddr::from_v6_bits(key),
+    };
+    // `len` came from a valid network of the same version
+    Network::new(addr, len).unwrap()
+}
+
+fn common_prefix_len(a: u128, b: u128) -> u8 {
+    (a ^ b).leading_zeros() as u8
+}
+
+fn prefix_mask(len: u8) -> u128 {
+    u128::MAX.checked_shl(128 - u32::from(len)).unwrap_or(0)
+}
+
+// Bit `index` counted from the most significant one
+fn bit_at(key: u128, index: u8) -> usize {
+    ((key >> (127 - u32::from(index))) & 1) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::closures::rng::Rng;
+
+    fn net(s: &str) -> Network {
+        s.parse().unwrap()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    // Prefixes are drawn from a small pool of addresses so that many of them nest
+    fn random_network(rng: &mut Rng) -> Network {
+        let pool = ["10.1.2.3", "10.1.130.7", "10.200.0.1", "192.168.1.1"];
+        let pool_v6 = ["2001:db8::1", "2001:db8:8000::5", "fe80::1"];
+
+        if rng.chance(0.5) {
+            let addr = ip(pool[(rng.next_u64() % 4) as usize]);
+            Network::new(addr, (rng.next_u64() % 33) as u8).unwrap()
+        } else {
+            let addr = ip(pool_v6[(rng.next_u64() % 3) as usize]);
+            Network::new(addr, (rng.next_u64() % 129) as u8).unwrap()
+        }
+    }
+
+    fn brute_force_lookup<'a>(
+        routes: &'a [(Network, u32)],
+        addr: &IpAddr,
+    ) -> Option<(Network, &'a u32)> {
+        routes
+            .iter()
+            .filter(|(network, _)| network.contains(addr))
+            .max_by_key(|(network, _)| network.prefix_len())
+            .map(|(network, hop)| (*network, hop))
+    }
+
+    #[test]
+    fn matches_a_brute_force_reference() {
+        let mut rng = Rng::seeded(5);
+        let mut table = RoutingTable::new();
+        let mut reference: Vec<(Network, u32)> = Vec::new();
+
+        for step in 0..2_000 {
+            let network = random_network(&mut rng);
+            let existing = reference.iter().position(|(n, _)| *n == network);
+
+            if rng.chance(0.3) {
+                let expected = existing.map(|i| reference.remove(i).1);
+                assert_eq!(table.withdraw(&network), expected);
+            } else {
+                let expected = existing.map(|i| reference.remove(i).1);
+                reference.push((network, step));
+                assert_eq!(table.insert(network, step), expected);
+            }
+            assert_eq!(table.len(), reference.len());
+
+            let probe = random_network(&mut rng).hosts().next().unwrap();
+            assert_eq!(table.lookup(&probe), brute_force_lookup(&reference, &probe));
+        }
+
+        reference.sort();
+        let routes: Vec<(Network, u32)> = table.iter().map(|(n, hop)| (n, *hop)).collect();
+        assert_eq!(routes, reference);
+    }
+
+    #[test]
+    fn looks_up_the_longest_prefix() {
+        let mut table = RoutingTable::new();
+        table.insert(net("0.0.0.0/0"), "default");
+        table.insert(net("10.0.0.0/8"), "core");
+        table.insert(net("10.1.0.0/16"), "branch");
+        table.insert(net("2001:db8::/32"), "v6");
+
+        assert_eq!(
+            table.lookup(&ip("10.1.2.3")),
+            Some((net("10.1.0.0/16"), &"branch"))
+        );
+        assert_eq!(
+            table.lookup(&ip("10.2.0.1")),
+            Some((net("10.0.0.0/8"), &"core"))
+        );
+        assert_eq!(
+            table.lookup(&ip("8.8.8.8")),
+            Some((net("0.0.0.0/0"), &"default"))
+        );
+        assert_eq!(
+            table.lookup(&ip("2001:db8::1")),
+            Some((net("2001:db8::/32"), &"v6"))
+        );
+        assert_eq!(table.lookup(&ip("2001:db9::1")), None);
+
+        assert_eq!(table.withdraw(&net("10.1.0.0/16")), Some("branch"));
+        assert_eq!(table.withdraw(&net("10.1.0.0/16")), None);
+        assert_eq!(
+            table.lookup(&ip("10.1.2.3")),
+            Some((net("10.0.0.0/8"), &"core"))
+        );
+    }
+
+    #[test]
+    fn iterates_in_prefix_order() {
+        let mut table = RoutingTable::new();
+        for (i, s) in ["::/0", "10.0.0.0/16", "10.0.0.0/8", "9.0.0.0/8", "10.0.0.1"]
+            .iter()
+            .enumerate()
+        {
+            table.insert(net(s), i);
+        }
+
+        let order: Vec<String> = table.iter().map(|(n, _)| n.to_string()).collect();
+        assert_eq!(
+            order,
+            vec![
+                "9.0.0.0/8",
+                "10.0.0.0/8",
+                "10.0.0.0/16",
+                "10.0.0.1/32",
+                "::/0"
+            ]
+        );
+    }
+}
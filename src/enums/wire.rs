@@ -0,0 +1,634 @@
+// Reading and writing addresses and packet headers in the form they have on the wire. All
+// multi byte numbers are big endian (network byte order). Only the parts needed to look at UDP
+// traffic are covered: IPv4 and IPv6 headers, and UDP headers with their checksum.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::IpAddr;
+
+pub const PROTOCOL_UDP: u8 = 17;
+
+const IPV4_HEADER_LEN: usize = 20;
+const IPV6_HEADER_LEN: usize = 40;
+const UDP_HEADER_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WireError {
+    Truncated { needed: usize, available: usize },
+    // Addresses are 4 or 16 bytes long
+    InvalidAddressLength(usize),
+    UnsupportedVersion(u8),
+    // The header or packet length field disagrees with the data, or is too large for its field
+    InvalidLength(usize),
+    InvalidChecksum { expected: u16, found: u16 },
+    // Source or destination do not match the IP version of the header
+    AddressVersion(IpAddr),
+    NotUdp(u8),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WireError::Truncated { needed, available } => write!(
+                f,
+                "packet is truncated, needed {} bytes but only {} are available",
+                needed, available
+            ),
+            WireError::InvalidAddressLength(len) => {
+                write!(f, "an address cannot be {} bytes long", len)
+            }
+            WireError::UnsupportedVersion(version) => {
+                write!(f, "unsupported IP version {}", version)
+            }
+            WireError::InvalidLength(len) => write!(f, "invalid length field {}", len),
+            WireError::InvalidChecksum { expected, found } => write!(
+                f,
+                "checksum is {:#06x} but should be {:#06x}",
+                found, expected
+            ),
+            WireError::AddressVersion(addr) => {
+                write!(f, "address {} does not match the IP version", addr)
+            }
+            WireError::NotUdp(protocol) => write!(f, "protocol {} is not UDP", protocol),
+        }
+    }
+}
+
+impl IpAddr {
+    // 4 bytes for IPv4, 16 for IPv6
+    pub fn to_bytes(self) -> Vec<u8> {
+        match self {
+            IpAddr::V4(a, b, c, d) => vec![a, b, c, d],
+            IpAddr::V6(segments) => segments.iter().flat_map(|s| s.to_be_bytes()).collect(),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<IpAddr, WireError> {
+        match bytes.len() {
+            4 => Ok(IpAddr::V4(bytes[0], bytes[1], bytes[2], bytes[3])),
+            16 => {
+                let mut segments = [0; 8];
+                for (segment, pair) in segments.iter_mut().zip(bytes.chunks(2)) {
+                    *segment = u16::from_be_bytes([pair[0], pair[1]]);
+                }
+                Ok(IpAddr::V6(segments))
+            }
+            len => Err(WireError::InvalidAddressLength(len)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ipv4Header {
+    pub type_of_service: u8,
+    // Header and payload together
+    pub total_length: u16,
+    pub identification: u16,
+    // The three flag bits
+    pub flags: u8,
+    pub fragment_offset: u16,
+    pub ttl: u8,
+    pub protocol: u8,
+    pub source: IpAddr,
+    pub destination: IpAddr,
+    // Length is a multiple of 4
+    pub options: Vec<u8>,
+}
+
+impl Ipv4Header {
+    // Fails when header and payload together are longer than the 16 bit length field allows
+    pub fn new(
+        source: IpAddr,
+        destination: IpAddr,
+        protocol: u8,
+        payload_len: u16,
+    ) -> Result<Ipv4Header, WireError> {
+        let total_length = (IPV4_HEADER_LEN as u16)
+            .checked_add(payload_len)
+            .ok_or_else(|| WireError::InvalidLength(IPV4_HEADER_LEN + usize::from(payload_len)))?;
+
+        Ok(Ipv4Header {
+            type_of_service: 0,
+            total_length,
+            identification: 0,
+            flags: 0,
+            fragment_offset: 0,
+            ttl: 64,
+            protocol,
+            source,
+            destination,
+            options: Vec::new(),
+        })
+    }
+
+    pub fn header_len(&self) -> usize {
+        IPV4_HEADER_LEN + self.options.len()
+    }
+
+    // Returns the header and the payload behind it. The header checksum is verified.
+    pub fn parse(bytes: &[u8]) -> Result<(Ipv4Header, &[u8]), WireError> {
+        check_len(bytes, IPV4_HEADER_LEN)?;
+
+        let version = bytes[0] >> 4;
+        if version != 4 {
+            return Err(WireError::UnsupportedVersion(version));
+        }
+
+        let header_len = usize::from(bytes[0] & 0x0f) * 4;
+        if header_len < IPV4_HEADER_LEN {
+            return Err(WireError::InvalidLength(header_len));
+        }
+        check_len(bytes, header_len)?;
+
+        let total_length = read_u16(bytes, 2);
+        if usize::from(total_length) < header_len {
+            return Err(WireError::InvalidLength(usize::from(total_length)));
+        }
+        check_len(bytes, usize::from(total_length))?;
+
+        let found = read_u16(bytes, 10);
+        let expected = checksum(&[&bytes[..10], &bytes[12..header_len]]);
+        if found != expected {
+            return Err(WireError::InvalidChecksum { expected, found });
+        }
+
+        let header = Ipv4Header {
+            type_of_service: bytes[1],
+            total_length,
+            identification: read_u16(bytes, 4),
+            flags: bytes[6] >> 5,
+            fragment_offset: read_u16(bytes, 6) & 0x1fff,
+            ttl: bytes[8],
+            protocol: bytes[9],
+            source: IpAddr::from_bytes(&bytes[12..16])?,
+            destination: IpAddr::from_bytes(&bytes[16..20])?,
+            options: bytes[IPV4_HEADER_LEN..header_len].to_vec(),
+        };
+        Ok((header, &bytes[header_len..usize::from(total_length)]))
+    }
+
+    // Appends the header with a freshly computed checksum
+    pub fn write(&self, out: &mut Vec<u8>) -> Result<(), WireError> {
+        check_version(&self.source, 32)?;
+        check_version(&self.destination, 32)?;
+        if !self.options.len().is_multiple_of(4) || self.header_len() > 60 {
+            return Err(WireError::InvalidLength(self.header_len()));
+        }
+
+        let mut header = Vec::with_capacity(self.header_len());
+        header.push(0x40 | (self.header_len() / 4) as u8);
+        header.push(self.type_of_service);
+        header.extend_from_slice(&self.total_length.to_be_bytes());
+        header.extend_from_slice(&self.identification.to_be_bytes());
+        let flags_and_offset = u16::from(self.flags) << 13 | (self.fragment_offset & 0x1fff);
+        header.extend_from_slice(&flags_and_offset.to_be_bytes());
+        header.push(self.ttl);
+        header.push(self.protocol);
+        header.extend_from_slice(&[0, 0]);
+        header.extend(self.source.to_bytes());
+        header.extend(self.destination.to_bytes());
+        header.extend_from_slice(&self.options);
+
+        let sum = checksum(&[&header]);
+        header[10..12].copy_from_slice(&sum.to_be_bytes());
+        out.extend(header);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ipv6Header {
+    pub traffic_class: u8,
+    // Only the lowest 20 bits are used
+    pub flow_label: u32,
+    pub payload_length: u16,
+    pub next_header: u8,
+    pub hop_limit: u8,
+    pub source: IpAddr,
+    pub destination: IpAddr,
+}
+
+impl Ipv6Header {
+    pub fn new(
+        source: IpAddr,
+        destination: IpAddr,
+        next_header: u8,
+        payload_length: u16,
+    ) -> Ipv6Header {
+        Ipv6Header {
+            traffic_class: 0,
+            flow_label: 0,
+            payload_length,
+            next_header,
+            hop_limit: 64,
+            source,
+            destination,
+        }
+    }
+
+    // Returns the header and the payload behind it. Extension headers are not followed.
+    pub fn parse(bytes: &[u8]) -> Result<(Ipv6Header, &[u8]), WireError> {
+        check_len(bytes, IPV6_HEADER_LEN)?;
+
+        let version = bytes[0] >> 4;
+        if version != 6 {
+            return Err(WireError::UnsupportedVersion(version));
+        }
+
+        let first_word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let payload_length = read_u16(bytes, 4);
+        let end = IPV6_HEADER_LEN + usize::from(payload_length);
+        check_len(bytes, end)?;
+
+        let header = Ipv6Header {
+            traffic_class: (first_word >> 20) as u8,
+            flow_label: first_word & 0x000f_ffff,
+            payload_length,
+            next_header: bytes[6],
+            hop_limit: bytes[7],
+            source: IpAddr::from_bytes(&bytes[8..24])?,
+            destination: IpAddr::from_bytes(&bytes[24..40])?,
+        };
+        Ok((header, &bytes[IPV6_HEADER_LEN..end]))
+    }
+
+    pub fn write(&self, out: &mut Vec<u8>) -> Result<(), WireError> {
+        check_version(&self.source, 128)?;
+        check_version(&self.destination, 128)?;
+
+        let first_word =
+            6 << 28 | u32::from(self.traffic_class) << 20 | (self.flow_label & 0x000f_ffff);
+        out.extend_from_slice(&first_word.to_be_bytes());
+        out.extend_from_slice(&self.payload_length.to_be_bytes());
+        out.push(self.next_header);
+        out.push(self.hop_limit);
+        out.extend(self.source.to_bytes());
+        out.extend(self.destination.to_bytes());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UdpHeader {
+    pub source_port: u16,
+    pub destination_port: u16,
+    // Header and payload together
+    pub length: u16,
+    // 0 means no checksum, which only IPv4 allows
+    pub checksum: u16,
+}
+
+impl UdpHeader {
+    // Fails when header and payload together are longer than the 16 bit length field allows
+    pub fn new(
+        source_port: u16,
+        destination_port: u16,
+        payload_len: u16,
+    ) -> Result<UdpHeader, WireError> {
+        let length = (UDP_HEADER_LEN as u16)
+            .checked_add(payload_len)
+            .ok_or_else(|| WireError::InvalidLength(UDP_HEADER_LEN + usize::from(payload_len)))?;
+
+        Ok(UdpHeader {
+            source_port,
+            destination_port,
+            length,
+            checksum: 0,
+        })
+    }
+
+    // Returns the header and the payload behind it. The checksum needs the IP addresses,
+    // see `verify_checksum`.
+    pub fn parse(bytes: &[u8]) -> Result<(UdpHeader, &[u8]), WireError> {
+        check_len(bytes, UDP_HEADER_LEN)?;
+
+        let length = read_u16(bytes, 4);
+        if usize::from(length) < UDP_HEADER_LEN {
+            return Err(WireError::InvalidLength(usize::from(length)));
+        }
+        check_len(bytes, usize::from(length))?;
+
+        let header = UdpHeader {
+            source_port: read_u16(bytes, 0),
+            destination_port: read_u16(bytes, 2),
+            length,
+            checksum: read_u16(bytes, 6),
+        };
+        Ok((header, &bytes[UDP_HEADER_LEN..usize::from(length)]))
+    }
+
+    pub fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.source_port.to_be_bytes());
+        out.extend_from_slice(&self.destination_port.to_be_bytes());
+        out.extend_from_slice(&self.length.to_be_bytes());
+        out.extend_from_slice(&self.checksum.to_be_bytes());
+    }
+
+    // The checksum covers a pseudo header made of the IP addresses, the protocol and the
+    // length, then the UDP header (with a zero checksum) and the payload
+    pub fn compute_checksum(&self, source: &IpAddr, destination: &IpAddr, payload: &[u8]) -> u16 {
+        let mut pseudo_header = source.to_bytes();
+        pseudo_header.extend(destination.to_bytes());
+        if source.is_ipv4() {
+            pseudo_header.extend_from_slice(&[0, PROTOCOL_UDP]);
+            pseudo_header.extend_from_slice(&self.length.to_be_bytes());
+        } else {
+            pseudo_header.extend_from_slice(&u32::from(self.length).to_be_bytes());
+            pseudo_header.extend_from_slice(&[0, 0, 0, PROTOCOL_UDP]);
+        }
+
+        let header = UdpHeader {
+            checksum: 0,
+            ..self.clone()
+        };
+        let mut header_bytes = Vec::with_capacity(UDP_HEADER_LEN);
+        header.write(&mut header_bytes);
+
+        // A computed 0 is sent as 0xffff, 0 is reserved for "no checksum"
+        match checksum(&[&pseudo_header, &header_bytes, payload]) {
+            0 => 0xffff,
+            sum => sum,
+        }
+    }
+
+    pub fn verify_checksum(
+        &self,
+        source: &IpAddr,
+        destination: &IpAddr,
+        payload: &[u8],
+    ) -> Result<(), WireError> {
+        if self.checksum == 0 && source.is_ipv4() {
+            return Ok(());
+        }
+
+        let expected = self.compute_checksum(source, destination, payload);
+        if self.checksum != expected {
+            return Err(WireError::InvalidChecksum {
+                expected,
+                found: self.checksum,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IpHeader {
+    V4(Ipv4Header),
+    V6(Ipv6Header),
+}
+
+impl IpHeader {
+    pub fn source(&self) -> IpAddr {
+        match self {
+            IpHeader::V4(header) => header.source,
+            IpHeader::V6(header) => header.source,
+        }
+    }
+
+    pub fn destination(&self) -> IpAddr {
+        match self {
+            IpHeader::V4(header) => header.destination,
+            IpHeader::V6(header) => header.destination,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UdpPacket<'a> {
+    pub ip: IpHeader,
+    pub udp: UdpHeader,
+    pub payload: &'a [u8],
+}
+
+// Parses a captured IP packet carrying UDP, checking every length and checksum on the way
+pub fn parse_udp_packet(bytes: &[u8]) -> Result<UdpPacket<'_>, WireError> {
+    check_len(bytes, 1)?;
+
+    let (ip, rest, protocol) = match bytes[0] >> 4 {
+        4 => {
+            let (header, rest) = Ipv4Header::parse(bytes)?;
+            let protocol = header.protocol;
+            (IpHeader::V4(header), rest, protocol)
+        }
+        6 => {
+            let (header, rest) = Ipv6Header::parse(bytes)?;
+            let protocol = header.next_header;
+            (IpHeader::V6(header), rest, protocol)
+        }
+        version => return Err(WireError::UnsupportedVersion(version)),
+    };
+    if protocol != PROTOCOL_UDP {
+        return Err(WireError::NotUdp(protocol));
+    }
+
+    let (udp, payload) = UdpHeader::parse(rest)?;
+    udp.verify_checksum(&ip.source(), &ip.destination(), payload)?;
+
+    Ok(UdpPacket { ip, udp, payload })
+}
+
+// Builds a complete IP packet with a UDP datagram, the IP version follows the addresses
+pub fn build_udp_packet(
+    source: (IpAddr, u16),
+    destination: (IpAddr, u16),
+    payload: &[u8],
+) -> Result<Vec<u8>, WireError> {
+    let (source, source_port) = source;
+    let (destination, destination_port) = destination;
+
+    let payload_len = u16::try_from(payload.len())
+        .map_err(|_| WireError::InvalidLength(UDP_HEADER_LEN + payload.len()))?;
+    let mut udp = UdpHeader::new(source_port, destination_port, payload_len)?;
+    let udp_len = udp.length;
+    udp.checksum = udp.compute_checksum(&source, &destination, payload);
+
+    let mut packet = Vec::new();
+    match source {
+        IpAddr::V4(..) => {
+            Ipv4Header::new(source, destination, PROTOCOL_UDP, udp_len)?.write(&mut packet)?
+        }
+        IpAddr::V6(_) => {
+            Ipv6Header::new(source, destination, PROTOCOL_UDP, udp_len).write(&mut packet)?
+        }
+    }
+    udp.write(&mut packet);
+    packet.extend_from_slice(payload);
+
+    Ok(packet)
+}
+
+fn check_len(bytes: &[u8], needed: usize) -> Result<(), WireError> {
+    if bytes.len() < needed {
+        return Err(WireError::Truncated {
+            needed,
+            available: bytes.len(),
+        });
+    }
+    Ok(())
+}
+
+fn check_version(addr: &IpAddr, bit_len: u8) -> Result<(), WireError> {
+    if addr.bit_len() != bit_len {
+        return Err(WireError::AddressVersion(*addr));
+    }
+    Ok(())
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+// The internet checksum (RFC 1071): one's complement of the one's complement sum of all 16 bit
+// words. The parts are summed as if they were one buffer, only the last one may have an odd
+// length.
+fn checksum(parts: &[&[u8]]) -> u16 {
+    let mut sum: u32 = 0;
+
+    for part in parts {
+        for word in part.chunks(2) {
+            let high = u32::from(word[0]) << 8;
+            let low = word.get(1).map_or(0, |b| u32::from(*b));
+            sum += high | low;
+        }
+    }
+
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn addresses_round_trip_in_network_byte_order() {
+        assert_eq!(ip("192.168.0.1").to_bytes(), vec![192, 168, 0, 1]);
+        assert_eq!(
+            ip("2001:db8::1").to_bytes(),
+            vec![0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+
+        for s in ["10.0.0.1", "::", "2001:db8::ff00:42:8329"].iter() {
+            assert_eq!(IpAddr::from_bytes(&ip(s).to_bytes()), Ok(ip(s)));
+        }
+        assert_eq!(
+            IpAddr::from_bytes(&[1, 2, 3]),
+            Err(WireError::InvalidAddressLength(3))
+        );
+    }
+
+    #[test]
+    fn ipv4_header_checksum_matches_a_known_packet() {
+        let mut bytes = vec![
+            0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0xb8, 0x61, 0xc0, 0xa8,
+            0x00, 0x01, 0xc0, 0xa8, 0x00, 0xc7,
+        ];
+        bytes.resize(0x73, 0);
+
+        let (header, payload) = Ipv4Header::parse(&bytes).unwrap();
+        assert_eq!(header.source, ip("192.168.0.1"));
+        assert_eq!(header.destination, ip("192.168.0.199"));
+        assert_eq!(header.flags, 0b010);
+        assert_eq!(header.protocol, PROTOCOL_UDP);
+        assert_eq!(payload.len(), 0x73 - 20);
+
+        let mut written = Vec::new();
+        header.write(&mut written).unwrap();
+        assert_eq!(written, &bytes[..20]);
+
+        bytes[11] = 0x62;
+        assert_eq!(
+            Ipv4Header::parse(&bytes),
+            Err(WireError::InvalidChecksum {
+                expected: 0xb861,
+                found: 0xb862
+            })
+        );
+    }
+
+    #[test]
+    fn udp_packets_round_trip_for_both_versions() {
+        let cases = [
+            (ip("10.0.0.1"), ip("10.0.0.2")),
+            (ip("2001:db8::1"), ip("2001:db8::2")),
+        ];
+
+        for (source, destination) in cases.iter() {
+            let bytes =
+                build_udp_packet((*source, 5353), (*destination, 53), b"hello, world!").unwrap();
+            let packet = parse_udp_packet(&bytes).unwrap();
+
+            assert_eq!(packet.ip.source(), *source);
+            assert_eq!(packet.ip.destination(), *destination);
+            assert_eq!(packet.udp.source_port, 5353);
+            assert_eq!(packet.udp.destination_port, 53);
+            assert_eq!(packet.payload, b"hello, world!");
+            assert_ne!(packet.udp.checksum, 0);
+        }
+    }
+
+    #[test]
+    fn corrupted_packets_are_rejected() {
+        let mut bytes =
+            build_udp_packet((ip("10.0.0.1"), 1000), (ip("10.0.0.2"), 2000), b"ping").unwrap();
+
+        assert_eq!(
+            parse_udp_packet(&bytes[..30]),
+            Err(WireError::Truncated {
+                needed: 32,
+                available: 30
+            })
+        );
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(matches!(
+            parse_udp_packet(&bytes),
+            Err(WireError::InvalidChecksum { .. })
+        ));
+
+        assert_eq!(
+            build_udp_packet((ip("10.0.0.1"), 1), (ip("::1"), 2), b""),
+            Err(WireError::AddressVersion(ip("::1")))
+        );
+        assert_eq!(
+            parse_udp_packet(&[0x50]),
+            Err(WireError::UnsupportedVersion(5))
+        );
+    }
+
+    #[test]
+    fn oversized_payloads_are_rejected() {
+        let (a, b) = (ip("10.0.0.1"), ip("10.0.0.2"));
+
+        assert_eq!(
+            Ipv4Header::new(a, b, PROTOCOL_UDP, u16::MAX - 19),
+            Err(WireError::InvalidLength(usize::from(u16::MAX) + 1))
+        );
+        assert_eq!(
+            Ipv4Header::new(a, b, PROTOCOL_UDP, u16::MAX - 20).map(|h| h.total_length),
+            Ok(u16::MAX)
+        );
+        assert_eq!(
+            UdpHeader::new(1, 2, u16::MAX),
+            Err(WireError::InvalidLength(usize::from(u16::MAX) + 8))
+        );
+        assert_eq!(UdpHeader::new(1, 2, 100).map(|h| h.length), Ok(108));
+
+        let payload = vec![0; usize::from(u16::MAX) - 8 - 20 + 1];
+        assert_eq!(
+            build_udp_packet((a, 1), (b, 2), &payload),
+            Err(WireError::InvalidLength(usize::from(u16::MAX) + 1))
+        );
+        assert!(build_udp_packet((a, 1), (b, 2), &payload[1..]).is_ok());
+        assert!(build_udp_packet((ip("::1"), 1), (ip("::2"), 2), &payload).is_ok());
+    }
+}
@@ -80,3 +80,90 @@ pub mod traits {
         }
     }
 }
+
+pub mod number {
+    use std::fmt::Debug;
+    use std::ops::{Add, Mul, Sub};
+
+    // Trait bounds let generic code do arithmetic on any numeric type. The checked and
+    // saturating methods mirror the inherent integer methods of the same name; floats
+    // treat results that are not finite as an overflow.
+    pub trait Number:
+        Copy + PartialOrd + Debug + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+    {
+        fn zero() -> Self;
+        fn checked_add(self, other: Self) -> Option<Self>;
+        fn checked_sub(self, other: Self) -> Option<Self>;
+        fn checked_mul(self, other: Self) -> Option<Self>;
+        fn saturating_mul(self, other: Self) -> Self;
+        fn to_f64(self) -> f64;
+    }
+
+    macro_rules! impl_number_for_integer {
+        ($($t:ty),*) => {
+            $(
+                impl Number for $t {
+                    fn zero() -> $t {
+                        0
+                    }
+
+                    fn checked_add(self, other: $t) -> Option<$t> {
+                        <$t>::checked_add(self, other)
+                    }
+
+                    fn checked_sub(self, other: $t) -> Option<$t> {
+                        <$t>::checked_sub(self, other)
+                    }
+
+                    fn checked_mul(self, other: $t) -> Option<$t> {
+                        <$t>::checked_mul(self, other)
+                    }
+
+                    fn saturating_mul(self, other: $t) -> $t {
+                        <$t>::saturating_mul(self, other)
+                    }
+
+                    fn to_f64(self) -> f64 {
+                        self as f64
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_number_for_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+    macro_rules! impl_number_for_float {
+        ($($t:ty),*) => {
+            $(
+                impl Number for $t {
+                    fn zero() -> $t {
+                        0.0
+                    }
+
+                    fn checked_add(self, other: $t) -> Option<$t> {
+                        Some(self + other).filter(|v| v.is_finite())
+                    }
+
+                    fn checked_sub(self, other: $t) -> Option<$t> {
+                        Some(self - other).filter(|v| v.is_finite())
+                    }
+
+                    fn checked_mul(self, other: $t) -> Option<$t> {
+                        Some(self * other).filter(|v| v.is_finite())
+                    }
+
+                    fn saturating_mul(self, other: $t) -> $t {
+                        (self * other).clamp(<$t>::MIN, <$t>::MAX)
+                    }
+
+                    fn to_f64(self) -> f64 {
+                        self as f64
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_number_for_float!(f32, f64);
+}
@@ -1,4 +1,5 @@
 mod closures;
+mod enums;
 mod generics_traits_lifetimes;
 mod iterators;
 mod smart_pointers;
@@ -9,8 +10,20 @@ use std::fs::File;
 use std::io;
 use std::io::Read;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
 use crate::closures as ClosureModule;
+use crate::enums::acl;
+use crate::enums::acl::{Acl, AclError};
+use crate::enums::collection::{QuarterCollection, Series};
+use crate::enums::money;
+use crate::enums::money::{Coin, Denomination, Money, UsState};
+use crate::enums::network::Network;
+use crate::enums::routing::RoutingTable;
+use crate::enums::wire;
+use crate::enums::IpAddr;
 use crate::generics_traits_lifetimes::generics as Generics;
 use crate::generics_traits_lifetimes::traits as Traits;
 use crate::generics_traits_lifetimes::traits::Summary;
@@ -18,41 +31,322 @@ use crate::iterators as Iterators;
 use crate::smart_pointers as SmartPointers;
 use crate::smart_pointers::BoxList;
 use crate::smart_pointers::RcList::{Cons, Nil};
+use crate::structs::auth::Authenticator;
+use crate::structs::packing;
+use crate::structs::quadtree::QuadTree;
 use crate::structs::rectangle;
+use crate::structs::render::Scene;
+use crate::structs::shapes;
+use crate::structs::shapes::{Circle, Point, Polygon, Shape, Triangle};
+use crate::structs::user_registry::UserRegistry;
 use crate::structs::user_struct;
-
-#[allow(unused_variables)]
-#[allow(dead_code)]
-#[derive(Debug)]
-enum UsState {
-    Alabama,
-    Alaska,
-}
-
-#[allow(unused_variables)]
-#[allow(dead_code)]
-#[derive(Debug)]
-enum Coin {
-    Penny,
-    Nickel,
-    Dime,
-    Quarter(UsState),
-}
+use crate::structs::user_struct::{UserBuilder, UserPatch};
 
 #[allow(unused_variables)]
 #[allow(dead_code)]
 fn main() {
     println!("\n******************Structs*******************\n");
-    let ameer =
-        user_struct::build_user(String::from("ameer"), String::from("ameernormie@gmail.com"));
+    let mut ameer =
+        user_struct::build_user(String::from("ameer"), String::from("ameernormie@gmail.com"))
+            .unwrap();
+
+    println!("Is user active {}", ameer.is_active());
+    if let Err(e) = ameer.record_sign_in() {
+        println!("{} can't sign in: {}", ameer.username(), e);
+    }
+    ameer.activate().unwrap();
+    ameer.record_sign_in().unwrap();
+    println!(
+        "{} <{}> is active {} and signed in {} time(s)",
+        ameer.username(),
+        ameer.email(),
+        ameer.is_active(),
+        ameer.sign_in_count()
+    );
+    ameer.deactivate().unwrap();
+
+    match user_struct::build_user(String::from("a b"), String::from("not an email")) {
+        Ok(user) => println!("built user {}", user.username()),
+        Err(e) => println!("could not build user: {}", e),
+    }
+
+    let mut registry = UserRegistry::new();
+    let ameer_id = registry
+        .register(String::from("ameer"), String::from("ameernormie@gmail.com"))
+        .unwrap();
+    let hamza_id = registry
+        .register(String::from("hamza"), String::from("hamza@example.com"))
+        .unwrap();
+
+    match registry.register(String::from("Ameer"), String::from("other@example.com")) {
+        Ok(id) => println!("registered user {}", id),
+        Err(e) => println!("could not register user: {}", e),
+    }
+    if let Err(e) = registry.update_email(hamza_id, String::from("AmeerNormie@gmail.com")) {
+        println!("could not update email: {}", e);
+    }
+
+    registry
+        .update_username(ameer_id, String::from("ameer_hamza"))
+        .unwrap();
+    registry.activate(ameer_id).unwrap();
+    registry.deactivate(ameer_id).unwrap();
+    if let Some((id, user)) = registry.find_by_email("AMEERNORMIE@gmail.com") {
+        println!("user {} is {} <{}>", id, user.username(), user.email());
+    }
+
+    let removed = registry.remove(hamza_id).unwrap();
+    println!(
+        "removed {}, registry has {} user(s)",
+        removed.username(),
+        registry.len()
+    );
+    println!(
+        "username hamza is free again {}",
+        registry.find_by_username("hamza").is_none()
+    );
+
+    let mut auth = Authenticator::new(registry);
+    auth.set_session_ttl(Duration::from_secs(60));
+    auth.set_max_failed_attempts(3);
+    auth.registry_mut().activate(ameer_id).unwrap();
+    auth.set_password(ameer_id, "correct horse battery")
+        .unwrap();
+
+    let session = auth
+        .sign_in("ameer_hamza", "correct horse battery")
+        .unwrap();
+    println!(
+        "signed in with session {}..., token belongs to user {:?}, sign in count {}",
+        &session.token[..8],
+        auth.validate(&session.token),
+        auth.registry().get(ameer_id).unwrap().sign_in_count()
+    );
+    auth.sign_out(&session.token);
+
+    for _ in 0..3 {
+        if let Err(e) = auth.sign_in("ameer_hamza", "wrong password") {
+            println!("sign in failed: {}", e);
+        }
+    }
+    println!("account locked {}", auth.is_locked(ameer_id));
+    auth.unlock(ameer_id);
+
+    let session = auth
+        .sign_in("ameer_hamza", "correct horse battery")
+        .unwrap();
+    auth.deactivate(ameer_id).unwrap();
+    println!(
+        "after deactivating the user the session gives {:?}",
+        auth.validate(&session.token)
+    );
+    auth.registry_mut().activate(ameer_id).unwrap();
+
+    let admin = UserBuilder::new()
+        .username(String::from("admin"))
+        .email(String::from("admin@example.com"))
+        .sign_in_count(0)
+        .active(true)
+        .build()
+        .unwrap();
+    let admin_id = auth.registry_mut().insert(admin).unwrap();
+
+    let patch = UserPatch {
+        email: Some(String::from("root@example.com")),
+        active: Some(false),
+        ..UserPatch::default()
+    };
+    for change in auth.registry_mut().apply_patch(admin_id, &patch).unwrap() {
+        println!(
+            "changed {} from {} to {}",
+            change.field, change.old, change.new
+        );
+    }
+    if let Ok(admin) = auth.remove(admin_id) {
+        println!(
+            "removed {}, {} users left",
+            admin.username(),
+            auth.registry().len()
+        );
+    }
 
-    println!("Is user active {}", ameer.active);
+    match UserBuilder::new()
+        .email(String::from("x@example.com"))
+        .build()
+    {
+        Ok(user) => println!("built user {}", user.username()),
+        Err(e) => println!("could not build user: {}", e),
+    }
 
     let rect = rectangle::get_rect(30, 30);
     rect.print_rect();
-    println!("The area of rectangle is {}", rect.area());
+    println!("The area of rectangle is {:?}", rect.area());
     let a_square_rect = rectangle::get_square_rect(10);
     println!("The square rectangle is {:#?}", a_square_rect);
+    println!(
+        "Can rect hold the square {}, perimeter of rect is {:?}",
+        rect.can_hold(&a_square_rect),
+        rect.perimeter()
+    );
+
+    let moved = a_square_rect.translate(25, 25).unwrap();
+    println!(
+        "Square moved to ({}, {}) with size {}x{}, right edge {} and bottom edge {}",
+        moved.x(),
+        moved.y(),
+        moved.width(),
+        moved.height(),
+        moved.right(),
+        moved.bottom()
+    );
+    println!(
+        "Rect intersects moved square {}, intersection {:?}",
+        rect.intersects(&moved),
+        rect.intersection(&moved)
+    );
+    println!(
+        "Union of rect and moved square {:?}, bounding box of all three {:?}",
+        rect.union(&moved),
+        rectangle::bounding_box(&[rect, a_square_rect, moved])
+    );
+    print!("{}", Scene::fit(&[rect, moved]).to_ascii(3));
+    println!(
+        "Rect contains point (29, 29) {}, contains the square {}, doubled square {:?}",
+        rect.contains_point(29, 29),
+        rect.contains(&a_square_rect),
+        a_square_rect.scale(2)
+    );
+
+    let screen: rectangle::Rectangle<u32> = rectangle::get_rect(u32::MAX, 2);
+    println!(
+        "Huge pixel rectangle has checked area {:?} and saturating area {}",
+        screen.checked_area(),
+        screen.saturating_area()
+    );
+    println!(
+        "A rectangle past the largest u32 is {:?}",
+        rectangle::Rectangle::checked_new(u32::MAX - 10, 0, 20, 1)
+    );
+    let centered: rectangle::Rectangle = rectangle::Rectangle::new(-15, -15, 30, 30);
+    println!(
+        "Rectangle centered on the origin contains (0, 0) {}",
+        centered.contains_point(0, 0)
+    );
+    let sheet = rectangle::Rectangle::new(0.0, 0.0, 0.21, 0.297);
+    println!(
+        "An A4 sheet covers {:.4} square meters",
+        sheet.area().unwrap_or(0.0)
+    );
+
+    let sprites = vec![
+        rectangle::get_rect(64, 64),
+        rectangle::get_rect(32, 64),
+        rectangle::get_rect(32, 32),
+        rectangle::get_rect(128, 16),
+        rectangle::get_rect(200, 10),
+    ];
+    for algorithm in [packing::Algorithm::Shelf, packing::Algorithm::MaxRects].iter() {
+        match packing::pack(128, 128, &sprites, *algorithm) {
+            Ok(atlas) => println!(
+                "{:?} packing uses {:.0}% of the atlas: {:?}",
+                algorithm,
+                atlas.utilization * 100.0,
+                atlas.placements
+            ),
+            Err(e) => println!("{:?} packing failed: {}", algorithm, e),
+        }
+    }
+    match packing::pack(256, 128, &sprites, packing::Algorithm::MaxRects) {
+        Ok(atlas) => {
+            println!(
+                "A wider atlas fits all sprites at {:.0}%",
+                atlas.utilization * 100.0
+            );
+
+            let mut scene = Scene::new(256, 128);
+            for placement in &atlas.placements {
+                scene.add(placement.rect);
+            }
+            println!("{}", scene.to_ascii(8));
+
+            let svg_path = std::env::temp_dir().join("atlas.svg");
+            match scene.write_svg(&svg_path) {
+                Ok(()) => println!("Atlas written to {}", svg_path.display()),
+                Err(e) => println!("Could not write the atlas: {}", e),
+            }
+        }
+        Err(e) => println!("Packing failed: {}", e),
+    }
+
+    // A quadtree finds the rectangles in a region without checking every one of them
+    let mut rng = ClosureModule::rng::Rng::seeded(2020);
+    let mut tree = QuadTree::new(rectangle::get_rect(1_000, 1_000));
+    let mut ids = Vec::new();
+    for _ in 0..200 {
+        let shape = rectangle::Rectangle::new(
+            (rng.next_u64() % 950) as u32,
+            (rng.next_u64() % 950) as u32,
+            1 + (rng.next_u64() % 50) as u32,
+            1 + (rng.next_u64() % 50) as u32,
+        );
+        ids.push(tree.insert(shape).unwrap());
+    }
+    let region = rectangle::Rectangle::new(100, 100, 200, 200);
+    println!(
+        "{} of {} rectangles overlap {:?}, the one nearest to (500, 500) is {:?}",
+        tree.query(&region).len(),
+        tree.len(),
+        region,
+        tree.nearest(500, 500)
+    );
+    let first_id = ids[0];
+    tree.remove(first_id);
+    println!(
+        "After removing rectangle {} the tree holds {} rectangles, lookup gives {:?}",
+        first_id,
+        tree.len(),
+        tree.get(first_id)
+    );
+
+    let wheel = Circle::new(Point::new(60.0, 10.0), 10.0);
+    let roof = Polygon::new(vec![
+        Point::new(50.0, 30.0),
+        Point::new(70.0, 30.0),
+        Point::new(70.0, 50.0),
+        Point::new(60.0, 40.0),
+        Point::new(50.0, 50.0),
+    ])
+    .unwrap();
+    println!(
+        "Circle at {:?} with radius {}, polygon with {} vertices",
+        wheel.center(),
+        wheel.radius(),
+        roof.vertices().len()
+    );
+    let layout: Vec<Box<dyn Shape>> = vec![
+        Box::new(rectangle::get_rect(40u32, 20)),
+        Box::new(wheel),
+        Box::new(Triangle::new(
+            Point::new(0.0, 30.0),
+            Point::new(40.0, 30.0),
+            Point::new(20.0, 50.0),
+        )),
+        Box::new(roof),
+    ];
+    for shape in layout.iter() {
+        println!(
+            "Shape with area {:.1}, perimeter {:.1}, contains (60, 35) {}, bounding box {:?}",
+            shape.area(),
+            shape.perimeter(),
+            shape.contains_point(60.0, 35.0),
+            shape.bounding_box()
+        );
+    }
+    println!(
+        "The layout covers {:.1} square units inside {:?}",
+        shapes::total_area(&layout),
+        shapes::bounding_box(&layout)
+    );
 
     println!("\n\n\n******************Enums*******************\n");
     println!("******************Option Enum*******************\n");
@@ -76,6 +370,96 @@ fn main() {
         value_in_cents(Coin::Quarter(UsState::Alabama))
     );
 
+    let price: Money = "$37.09".parse().unwrap();
+    let paid = Money::from_dollars(50, 0).unwrap();
+    match paid.checked_sub(price) {
+        Some(change) => println!(
+            "Paying {} for {} gives {} back as {:?}",
+            paid,
+            price,
+            change,
+            money::make_change_in(change, &Denomination::ALL).unwrap()
+        ),
+        None => println!("{} is not enough to pay {}", paid, price),
+    }
+    let purse = [
+        Coin::Penny,
+        Coin::Nickel,
+        Coin::Dime,
+        Coin::Quarter(UsState::Alaska),
+        Coin::HalfDollar,
+        Coin::Dollar,
+    ];
+    let total = purse.iter().try_fold(Money::default(), |total, coin| {
+        total.checked_add(coin.value())
+    });
+    println!(
+        "A purse with one of every coin holds {:?}, a half dollar is a coin {}",
+        total.map(|total| total.to_string()),
+        Denomination::HalfDollar.is_coin()
+    );
+    let odd_coins = [
+        Money::from_cents(1),
+        Money::from_cents(3),
+        Money::from_cents(4),
+    ];
+    println!(
+        "With 1, 3 and 4 cent coins, 6 cents is best paid as {:?}",
+        money::make_change(Money::from_cents(6), &odd_coins)
+    );
+    println!(
+        "A dozen at {} each costs {:?}",
+        price,
+        price.checked_mul(12).map(|total| total.to_string())
+    );
+    if let Err(e) = "12.345".parse::<Money>() {
+        println!("{}", e);
+    }
+
+    let mut collection = QuarterCollection::new(Series::StatesAndTerritories);
+    for name in ["Alabama", "AK", "new york", "Guam", "ak", "Texas", "Narnia"].iter() {
+        match name.parse::<UsState>() {
+            Ok(state) => {
+                collection.add_coin(Coin::Quarter(state));
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+    collection.remove(UsState::Texas);
+    println!(
+        "The collection has {} ({}), spare {:?}, {} missing, {:.1}% complete, done {}",
+        collection
+            .owned()
+            .iter()
+            .map(|state| state.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        collection
+            .owned()
+            .iter()
+            .map(|state| state.abbreviation())
+            .collect::<Vec<_>>()
+            .join(" "),
+        collection.duplicates(),
+        collection.missing().len(),
+        collection.completion(),
+        collection.is_complete()
+    );
+    println!(
+        "Owns Texas {}, Alaska quarters {}, Puerto Rico is a territory {}",
+        collection.owns(UsState::Texas),
+        collection.count(UsState::Alaska),
+        UsState::PuertoRico.is_territory()
+    );
+    let mut states_only = QuarterCollection::new(Series::States);
+    for state in collection.owned() {
+        states_only.add(state);
+    }
+    println!(
+        "Counting only the 50 states the same quarters are {:.0}% of the set",
+        states_only.completion()
+    );
+
     let five = Some(5);
     let six = plus_one(five);
     println!("The six is {:?}", six);
@@ -96,6 +480,170 @@ fn main() {
         println!("The value is not three");
     }
 
+    println!("\n******************IP Addresses*******************\n");
+    for input in [
+        "127.0.0.1",
+        "192.168.1.20",
+        "2001:0DB8:0:0:0:0:0:1",
+        "fe80::1",
+        "1.2.3.256",
+    ]
+    .iter()
+    {
+        match input.parse::<IpAddr>() {
+            Ok(addr) => println!(
+                "{} is {}, ipv4 {}, loopback {}, private {}, multicast {}, link local {}",
+                input,
+                addr,
+                addr.is_ipv4(),
+                addr.is_loopback(),
+                addr.is_private(),
+                addr.is_multicast(),
+                addr.is_link_local()
+            ),
+            Err(e) => println!("{}", e),
+        }
+    }
+    let std_addr = std::net::IpAddr::from(IpAddr::V6([0xff02, 0, 0, 0, 0, 0, 0, 1]));
+    println!(
+        "As a std address {}, is ipv6 {}",
+        std_addr,
+        IpAddr::from(std_addr).is_ipv6()
+    );
+
+    for input in ["192.168.10.77/22", "2001:db8:abcd::/48", "10.0.0.0/33"].iter() {
+        let network: Network = match input.parse() {
+            Ok(network) => network,
+            Err(e) => {
+                println!("{} is not a network: {}", input, e);
+                continue;
+            }
+        };
+        let (first, last) = network.host_range();
+        println!(
+            "{} has address {}, prefix /{}, netmask {}, broadcast {}, hosts {} to {} ({} in total), contains 192.168.9.1 {}",
+            network,
+            network.address(),
+            network.prefix_len(),
+            network.netmask(),
+            network.broadcast(),
+            first,
+            last,
+            network.host_count(),
+            network.contains(&IpAddr::V4(192, 168, 9, 1))
+        );
+    }
+    let office: Network = "10.20.0.0/24".parse().unwrap();
+    for subnet in office.subnets(26).unwrap() {
+        println!(
+            "Subnet {} starts with hosts {:?}",
+            subnet,
+            subnet.hosts().take(2).collect::<Vec<_>>()
+        );
+    }
+    println!(
+        "The /16 around {} is {}, it contains the office network {}",
+        office,
+        office.supernet(16).unwrap(),
+        office.supernet(16).unwrap().contains_network(&office)
+    );
+
+    let acl_path = std::env::temp_dir().join("office_acl.txt");
+    let acl_rules = "# office access\nmode longest-prefix\nallow 10.20.0.0/24\ndeny 10.20.0.64/26\nallow 10.20.0.70-10.20.0.79\ndeny fe80::/10\n";
+    let acl = std::fs::write(&acl_path, acl_rules)
+        .map_err(AclError::from)
+        .and_then(|_| Acl::load(&acl_path));
+    match acl {
+        Ok(acl) => {
+            for addr in [
+                "10.20.0.5",
+                "10.20.0.65",
+                "10.20.0.75",
+                "10.30.0.1",
+                "fe80::1",
+            ]
+            .iter()
+            {
+                println!("{}", acl.evaluate(&addr.parse().unwrap()).explain());
+            }
+            println!(
+                "10.20.0.200 is allowed {}",
+                acl.is_allowed(&IpAddr::V4(10, 20, 0, 200))
+            );
+        }
+        Err(e) => println!("Could not load the access list: {}", e),
+    }
+    let mut guest_acl = Acl::new(acl::Mode::FirstMatch);
+    guest_acl.set_default(acl::Action::Allow);
+    guest_acl.push(acl::Action::Deny, acl::Target::Network(office));
+    println!(
+        "Guest list has {} rule(s): {}",
+        guest_acl.rules().len(),
+        guest_acl.evaluate(&IpAddr::V4(10, 20, 0, 1)).explain()
+    );
+
+    let mut routes = RoutingTable::new();
+    for (prefix, next_hop) in [
+        ("0.0.0.0/0", "isp uplink"),
+        ("10.0.0.0/8", "core router"),
+        ("10.20.0.0/16", "branch office"),
+        ("10.20.0.64/26", "lab switch"),
+        ("2001:db8::/32", "v6 tunnel"),
+    ]
+    .iter()
+    {
+        routes.insert(prefix.parse().unwrap(), *next_hop);
+    }
+    routes.withdraw(&"10.20.0.0/16".parse().unwrap());
+    for addr in [
+        "10.20.0.70",
+        "10.20.5.1",
+        "1.1.1.1",
+        "2001:db8::53",
+        "fe80::1",
+    ]
+    .iter()
+    {
+        match routes.lookup(&addr.parse().unwrap()) {
+            Some((network, next_hop)) => println!("{} goes to {} via {}", addr, next_hop, network),
+            None => println!("{} has no route", addr),
+        }
+    }
+    println!("Routing table with {} routes:", routes.len());
+    for (network, next_hop) in routes.iter() {
+        println!("  {} -> {}", network, next_hop);
+    }
+
+    let dns_query = wire::build_udp_packet(
+        (IpAddr::V4(10, 20, 0, 5), 53000),
+        (IpAddr::V4(10, 0, 0, 53), 53),
+        b"example.com?",
+    )
+    .unwrap();
+    println!(
+        "Encoded {} as {:?}, the UDP packet is {} bytes long",
+        IpAddr::V4(10, 0, 0, 53),
+        IpAddr::V4(10, 0, 0, 53).to_bytes(),
+        dns_query.len()
+    );
+    match wire::parse_udp_packet(&dns_query) {
+        Ok(packet) => println!(
+            "Decoded packet from {}:{} to {}:{} with checksum {:#06x} and payload {:?}",
+            packet.ip.source(),
+            packet.udp.source_port,
+            packet.ip.destination(),
+            packet.udp.destination_port,
+            packet.udp.checksum,
+            String::from_utf8_lossy(packet.payload)
+        ),
+        Err(e) => println!("Could not decode the packet: {}", e),
+    }
+    let mut damaged = dns_query.clone();
+    damaged[30] ^= 0x01;
+    if let Err(e) = wire::parse_udp_packet(&damaged) {
+        println!("Damaged packet: {}", e);
+    }
+
     println!("\n\n\n******************Collections*******************\n");
     println!("******************Vectors*******************\n");
     let mut v = vec![1, 2, 3];
@@ -261,9 +809,99 @@ fn main() {
     );
     println!("\n******************Closures*******************\n");
     let simulated_user_specified_value = 10;
-    let simulated_random_number = 7;
 
-    ClosureModule::generate_workout(simulated_user_specified_value, simulated_random_number);
+    let mut profile = ClosureModule::workout::UserProfile::new(32, 78.0, 1.80);
+    profile.preferences = vec![
+        ClosureModule::workout::Preference::Strength,
+        ClosureModule::workout::Preference::Flexibility,
+    ];
+
+    // Workouts completed on earlier runs are fed back into the generator
+    let today = ClosureModule::history::today();
+    let history_path = std::env::temp_dir().join("workout_history.txt");
+    let mut history = match ClosureModule::history::HistoryStore::open(&history_path) {
+        Ok(history) => Some(history),
+        Err(e) => {
+            println!("error while reading workout history {}", e);
+            None
+        }
+    };
+    if let Some(history) = &history {
+        profile.recent_workouts = history.recent_workouts(today, 7);
+        println!(
+            "{} exercises recorded in the history, {} of them in the last week",
+            history.sessions().len(),
+            profile.recent_workouts.len()
+        );
+    }
+
+    let plan = ClosureModule::generate_workout(&profile, simulated_user_specified_value);
+    print!("{}", plan);
+
+    if let Some(history) = &mut history {
+        if let Err(e) = history.record_plan(today, &plan) {
+            println!("error while saving workout history {}", e);
+        }
+    }
+
+    println!("\nA four week schedule with progressive overload and a deload week");
+    // Skip the slow simulated calculation so the demo does not wait for every new intensity
+    let mut generator =
+        ClosureModule::WorkoutGenerator::with_calculation(Ok, ClosureModule::rng::Rng::from_time());
+    let settings = ClosureModule::schedule::ScheduleSettings {
+        weeks: 4,
+        ..Default::default()
+    };
+    let schedule = ClosureModule::schedule::plan_schedule(&mut generator, &profile, &settings);
+    print!("{}", schedule);
+    for week in 1..=settings.weeks {
+        println!(
+            "volume of week {} is {}",
+            week,
+            schedule.weekly_volume(week)
+        );
+    }
+    print!("{}", schedule.to_csv());
+
+    println!("\nA bounded cacher only keeps the most recently used values");
+    let mut bounded_cacher = ClosureModule::Cacher::with_policy(
+        |num: u32| num * 2,
+        ClosureModule::EvictionPolicy::Lru { capacity: 2 },
+    );
+
+    for num in [1, 2, 1, 3, 2].iter() {
+        println!("doubled {} is {}", num, bounded_cacher.value(*num));
+    }
+
+    println!(
+        "bounded cacher holds {} values after {} misses and {} hits",
+        bounded_cacher.len(),
+        bounded_cacher.misses(),
+        bounded_cacher.hits()
+    );
+
+    println!("\nSharing a cacher between threads");
+    let shared_cacher = Arc::new(ClosureModule::SharedCacher::new(|num: u32| {
+        println!("calculating slowly...");
+        thread::sleep(Duration::from_millis(500));
+        num
+    }));
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let shared_cacher = Arc::clone(&shared_cacher);
+            thread::spawn(move || shared_cacher.value(simulated_user_specified_value))
+        })
+        .collect();
+
+    for handle in handles {
+        println!("value from worker thread {}", handle.join().unwrap());
+    }
+
+    println!(
+        "four threads asked for the same value, the calculation ran {} time(s)",
+        shared_cacher.misses()
+    );
 
     println!("\n******************Iterators*******************\n");
     let v1 = vec![1, 2, 3];
@@ -360,6 +998,8 @@ fn value_in_cents(coin: Coin) -> u8 {
             println!("the state of quarter is {:?}", state);
             25
         }
+        Coin::HalfDollar => 50,
+        Coin::Dollar => 100,
     }
 }
 
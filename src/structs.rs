@@ -1,52 +1,789 @@
+pub mod auth;
+pub mod packing;
+pub mod quadtree;
+pub mod render;
+pub mod shapes;
+pub mod user_registry;
+
 pub mod user_struct {
-    #[allow(dead_code)]
+    use std::fmt;
+
+    const USERNAME_MIN_LEN: usize = 3;
+    const USERNAME_MAX_LEN: usize = 32;
+
+    #[derive(Debug, Clone, PartialEq)]
     pub struct User {
         username: String,
         email: String,
         sign_in_count: u64,
-        pub active: bool,
+        active: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum UserError {
+        EmptyUsername,
+        InvalidUsername(String),
+        InvalidEmail(String),
+        AlreadyActive,
+        AlreadyInactive,
+        // Inactive users cannot sign in
+        Inactive,
+        MissingField(&'static str),
+    }
+
+    impl fmt::Display for UserError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                UserError::EmptyUsername => write!(f, "username must not be empty"),
+                UserError::InvalidUsername(username) => write!(
+                    f,
+                    "invalid username {:?}: use {} to {} letters, digits, '_', '-' or '.' starting with a letter",
+                    username, USERNAME_MIN_LEN, USERNAME_MAX_LEN
+                ),
+                UserError::InvalidEmail(email) => write!(f, "invalid email address {:?}", email),
+                UserError::AlreadyActive => write!(f, "user is already active"),
+                UserError::AlreadyInactive => write!(f, "user is already inactive"),
+                UserError::Inactive => write!(f, "user is not active"),
+                UserError::MissingField(field) => write!(f, "{} is required", field),
+            }
+        }
+    }
+
+    impl User {
+        // New users start inactive and without any sign ins
+        pub fn new(username: String, email: String) -> Result<User, UserError> {
+            validate_username(&username)?;
+            validate_email(&email)?;
+
+            Ok(User {
+                username,
+                email,
+                sign_in_count: 0,
+                active: false,
+            })
+        }
+
+        pub fn username(&self) -> &str {
+            &self.username
+        }
+
+        pub fn email(&self) -> &str {
+            &self.email
+        }
+
+        pub fn set_username(&mut self, username: String) -> Result<(), UserError> {
+            validate_username(&username)?;
+            self.username = username;
+            Ok(())
+        }
+
+        pub fn set_email(&mut self, email: String) -> Result<(), UserError> {
+            validate_email(&email)?;
+            self.email = email;
+            Ok(())
+        }
+
+        pub fn sign_in_count(&self) -> u64 {
+            self.sign_in_count
+        }
+
+        pub fn is_active(&self) -> bool {
+            self.active
+        }
+
+        // Returns the new sign in count
+        pub fn record_sign_in(&mut self) -> Result<u64, UserError> {
+            if !self.active {
+                return Err(UserError::Inactive);
+            }
+
+            self.sign_in_count += 1;
+            Ok(self.sign_in_count)
+        }
+
+        pub fn activate(&mut self) -> Result<(), UserError> {
+            if self.active {
+                return Err(UserError::AlreadyActive);
+            }
+
+            self.active = true;
+            Ok(())
+        }
+
+        pub fn deactivate(&mut self) -> Result<(), UserError> {
+            if !self.active {
+                return Err(UserError::AlreadyInactive);
+            }
+
+            self.active = false;
+            Ok(())
+        }
+    }
+
+    pub fn build_user(username: String, email: String) -> Result<User, UserError> {
+        User::new(username, email)
+    }
+
+    // Builds a user field by field, validating everything in `build`
+    #[derive(Debug, Clone, Default)]
+    pub struct UserBuilder {
+        username: Option<String>,
+        email: Option<String>,
+        sign_in_count: u64,
+        active: bool,
+    }
+
+    impl UserBuilder {
+        pub fn new() -> UserBuilder {
+            UserBuilder::default()
+        }
+
+        pub fn username(mut self, username: String) -> UserBuilder {
+            self.username = Some(username);
+            self
+        }
+
+        pub fn email(mut self, email: String) -> UserBuilder {
+            self.email = Some(email);
+            self
+        }
+
+        pub fn sign_in_count(mut self, sign_in_count: u64) -> UserBuilder {
+            self.sign_in_count = sign_in_count;
+            self
+        }
+
+        pub fn active(mut self, active: bool) -> UserBuilder {
+            self.active = active;
+            self
+        }
+
+        pub fn build(self) -> Result<User, UserError> {
+            let username = self.username.ok_or(UserError::MissingField("username"))?;
+            let email = self.email.ok_or(UserError::MissingField("email"))?;
+
+            // Struct update syntax: take the validated defaults and override the rest
+            Ok(User {
+                sign_in_count: self.sign_in_count,
+                active: self.active,
+                ..User::new(username, email)?
+            })
+        }
     }
 
-    pub fn build_user(username: String, email: String) -> User {
-        User {
-            username,
-            email,
-            sign_in_count: 1,
-            active: false,
+    // A partial update, only the fields that are `Some` are changed
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct UserPatch {
+        pub username: Option<String>,
+        pub email: Option<String>,
+        pub sign_in_count: Option<u64>,
+        pub active: Option<bool>,
+    }
+
+    // One entry of the audit returned by `User::apply`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct FieldChange {
+        pub field: &'static str,
+        pub old: String,
+        pub new: String,
+    }
+
+    impl User {
+        // Applies the patch and returns the fields whose value actually changed. The patch
+        // is validated first, so on error the user is left untouched.
+        pub fn apply(&mut self, patch: &UserPatch) -> Result<Vec<FieldChange>, UserError> {
+            if let Some(username) = &patch.username {
+                validate_username(username)?;
+            }
+            if let Some(email) = &patch.email {
+                validate_email(email)?;
+            }
+
+            let patched = User {
+                username: patch
+                    .username
+                    .clone()
+                    .unwrap_or_else(|| self.username.clone()),
+                email: patch.email.clone().unwrap_or_else(|| self.email.clone()),
+                sign_in_count: patch.sign_in_count.unwrap_or(self.sign_in_count),
+                active: patch.active.unwrap_or(self.active),
+            };
+
+            let mut changes = Vec::new();
+            let mut record = |field: &'static str, old: String, new: String| {
+                if old != new {
+                    changes.push(FieldChange { field, old, new });
+                }
+            };
+            record("username", self.username.clone(), patched.username.clone());
+            record("email", self.email.clone(), patched.email.clone());
+            record(
+                "sign_in_count",
+                self.sign_in_count.to_string(),
+                patched.sign_in_count.to_string(),
+            );
+            record(
+                "active",
+                self.active.to_string(),
+                patched.active.to_string(),
+            );
+
+            *self = patched;
+            Ok(changes)
+        }
+    }
+
+    // 3 to 32 ASCII letters, digits, '_', '-' or '.', starting with a letter
+    pub fn validate_username(username: &str) -> Result<(), UserError> {
+        if username.is_empty() {
+            return Err(UserError::EmptyUsername);
+        }
+
+        let starts_with_letter = username
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic());
+        let allowed_chars = username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+        let len = username.len();
+
+        if starts_with_letter
+            && allowed_chars
+            && (USERNAME_MIN_LEN..=USERNAME_MAX_LEN).contains(&len)
+        {
+            Ok(())
+        } else {
+            Err(UserError::InvalidUsername(String::from(username)))
+        }
+    }
+
+    // A pragmatic subset of RFC 5322: `local@domain.tld` without quoting or comments
+    pub fn validate_email(email: &str) -> Result<(), UserError> {
+        let invalid = || Err(UserError::InvalidEmail(String::from(email)));
+
+        let mut parts = email.split('@');
+        let (local, domain) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(local), Some(domain), None) => (local, domain),
+            _ => return invalid(),
+        };
+
+        let local_ok = !local.is_empty()
+            && local.len() <= 64
+            && !local.starts_with('.')
+            && !local.ends_with('.')
+            && !local.contains("..")
+            && local
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~.-".contains(c));
+        if !local_ok {
+            return invalid();
+        }
+
+        let labels: Vec<&str> = domain.split('.').collect();
+        let labels_ok = labels.len() >= 2
+            && labels.iter().all(|label| {
+                !label.is_empty()
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+        let tld = labels[labels.len() - 1];
+        let tld_ok = tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic());
+
+        if labels_ok && tld_ok {
+            Ok(())
+        } else {
+            invalid()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn user() -> User {
+            User::new(String::from("ameer"), String::from("ameernormie@gmail.com")).unwrap()
+        }
+
+        #[test]
+        fn rejects_bad_usernames() {
+            let email = String::from("a@example.com");
+
+            assert_eq!(
+                User::new(String::new(), email.clone()),
+                Err(UserError::EmptyUsername)
+            );
+            for username in ["ab", "1abc", "has space", "émile", &"a".repeat(33)].iter() {
+                assert_eq!(
+                    User::new(String::from(*username), email.clone()),
+                    Err(UserError::InvalidUsername(String::from(*username)))
+                );
+            }
+        }
+
+        #[test]
+        fn rejects_malformed_emails() {
+            for email in [
+                "",
+                "plainaddress",
+                "@example.com",
+                "a@@example.com",
+                "a@b@example.com",
+                ".a@example.com",
+                "a..b@example.com",
+                "a@example",
+                "a@-example.com",
+                "a@example..com",
+                "a@example.c",
+                "a b@example.com",
+            ]
+            .iter()
+            {
+                assert_eq!(
+                    User::new(String::from("ameer"), String::from(*email)),
+                    Err(UserError::InvalidEmail(String::from(*email))),
+                    "{} should be rejected",
+                    email
+                );
+            }
+
+            assert!(User::new(
+                String::from("ameer"),
+                String::from("first.last+tag@mail.example.org")
+            )
+            .is_ok());
+        }
+
+        #[test]
+        fn sign_ins_require_an_active_user() {
+            let mut user = user();
+
+            assert_eq!(user.record_sign_in(), Err(UserError::Inactive));
+
+            user.activate().unwrap();
+            assert_eq!(user.record_sign_in(), Ok(1));
+            assert_eq!(user.record_sign_in(), Ok(2));
+            assert_eq!(user.sign_in_count(), 2);
+        }
+
+        #[test]
+        fn builder_validates_and_fills_in_fields() {
+            let user = UserBuilder::new()
+                .username(String::from("ameer"))
+                .email(String::from("ameer@example.com"))
+                .active(true)
+                .sign_in_count(3)
+                .build()
+                .unwrap();
+
+            assert_eq!(user.username(), "ameer");
+            assert!(user.is_active());
+            assert_eq!(user.sign_in_count(), 3);
+
+            assert_eq!(
+                UserBuilder::new()
+                    .email(String::from("a@example.com"))
+                    .build(),
+                Err(UserError::MissingField("username"))
+            );
+            assert_eq!(
+                UserBuilder::new()
+                    .username(String::from("ameer"))
+                    .email(String::from("nope"))
+                    .build(),
+                Err(UserError::InvalidEmail(String::from("nope")))
+            );
+        }
+
+        #[test]
+        fn patch_changes_only_given_fields_and_reports_them() {
+            let mut user = user();
+            let patch = UserPatch {
+                email: Some(String::from("new@example.com")),
+                // Same value as before, so not part of the audit
+                username: Some(String::from("ameer")),
+                active: Some(true),
+                ..UserPatch::default()
+            };
+
+            let changes = user.apply(&patch).unwrap();
+
+            assert_eq!(
+                changes,
+                vec![
+                    FieldChange {
+                        field: "email",
+                        old: String::from("ameernormie@gmail.com"),
+                        new: String::from("new@example.com"),
+                    },
+                    FieldChange {
+                        field: "active",
+                        old: String::from("false"),
+                        new: String::from("true"),
+                    },
+                ]
+            );
+            assert_eq!(user.email(), "new@example.com");
+            assert_eq!(user.username(), "ameer");
+            assert!(user.apply(&UserPatch::default()).unwrap().is_empty());
+        }
+
+        #[test]
+        fn invalid_patch_leaves_user_untouched() {
+            let mut user = user();
+            let before = user.clone();
+            let patch = UserPatch {
+                email: Some(String::from("new@example.com")),
+                username: Some(String::from("x")),
+                ..UserPatch::default()
+            };
+
+            assert_eq!(
+                user.apply(&patch),
+                Err(UserError::InvalidUsername(String::from("x")))
+            );
+            assert_eq!(user, before);
+        }
+
+        #[test]
+        fn activate_and_deactivate_are_state_transitions() {
+            let mut user = user();
+
+            assert_eq!(user.deactivate(), Err(UserError::AlreadyInactive));
+            assert_eq!(user.activate(), Ok(()));
+            assert!(user.is_active());
+            assert_eq!(user.activate(), Err(UserError::AlreadyActive));
+            assert_eq!(user.deactivate(), Ok(()));
+            assert!(!user.is_active());
         }
     }
 }
 
 pub mod rectangle {
-    #[derive(Debug)]
-    pub struct Rectangle {
-        width: u32,
-        height: u32,
+    use crate::generics_traits_lifetimes::number::Number;
+
+    // A rectangle placed on a grid. `x` and `y` are the top left corner, the right and bottom
+    // edges are exclusive. Works with any `Number`, integers for pixel layouts and floats for
+    // physical units. Width and height are never negative and the right and bottom edges always
+    // fit into `T`. Without a type the coordinates are `i32`, so origins can be negative.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Rectangle<T = i32> {
+        x: T,
+        y: T,
+        width: T,
+        height: T,
     }
 
-    impl Rectangle {
-        pub fn area(&self) -> u32 {
-            self.width * self.height
+    fn min<T: PartialOrd>(a: T, b: T) -> T {
+        if b < a {
+            b
+        } else {
+            a
+        }
+    }
+
+    fn max<T: PartialOrd>(a: T, b: T) -> T {
+        if b > a {
+            b
+        } else {
+            a
+        }
+    }
+
+    impl<T: Number> Rectangle<T> {
+        // Negative sizes are treated as zero. Panics when the right or bottom edge does not fit
+        // into `T`, use `checked_new` for sizes that come from input.
+        pub fn new(x: T, y: T, width: T, height: T) -> Rectangle<T> {
+            match Rectangle::checked_new(x, y, width, height) {
+                Some(rect) => rect,
+                None => panic!(
+                    "rectangle at ({:?}, {:?}) with size {:?}x{:?} does not fit",
+                    x, y, width, height
+                ),
+            }
+        }
+
+        // `None` when the right or bottom edge does not fit into `T`
+        pub fn checked_new(x: T, y: T, width: T, height: T) -> Option<Rectangle<T>> {
+            let width = max(width, T::zero());
+            let height = max(height, T::zero());
+            x.checked_add(width)?;
+            y.checked_add(height)?;
+
+            Some(Rectangle {
+                x,
+                y,
+                width,
+                height,
+            })
+        }
+
+        pub fn x(&self) -> T {
+            self.x
+        }
+
+        pub fn y(&self) -> T {
+            self.y
+        }
+
+        pub fn width(&self) -> T {
+            self.width
+        }
+
+        pub fn height(&self) -> T {
+            self.height
+        }
+
+        pub fn right(&self) -> T {
+            self.x + self.width
+        }
+
+        pub fn bottom(&self) -> T {
+            self.y + self.height
+        }
+
+        // `None` if the area does not fit into `T`
+        pub fn checked_area(&self) -> Option<T> {
+            self.width.checked_mul(self.height)
+        }
+
+        // Same as `checked_area`. It used to saturate silently, use `saturating_area` for that.
+        pub fn area(&self) -> Option<T> {
+            self.checked_area()
+        }
+
+        // Clamped to the largest value of `T` instead of overflowing
+        pub fn saturating_area(&self) -> T {
+            self.width.saturating_mul(self.height)
+        }
+
+        // `None` if the perimeter does not fit into `T`
+        pub fn perimeter(&self) -> Option<T> {
+            let half = self.width.checked_add(self.height)?;
+            half.checked_add(half)
         }
 
         pub fn print_rect(&self) {
             println!("The rectangle is {:#?}", self);
         }
 
-        pub fn square(size: u32) -> Rectangle {
-            Rectangle {
-                width: size,
-                height: size
+        pub fn square(size: T) -> Rectangle<T> {
+            Rectangle::new(T::zero(), T::zero(), size, size)
+        }
+
+        // Whether `other` would fit inside this rectangle, wherever the two are placed
+        pub fn can_hold(&self, other: &Rectangle<T>) -> bool {
+            self.width > other.width && self.height > other.height
+        }
+
+        pub fn contains_point(&self, x: T, y: T) -> bool {
+            x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+        }
+
+        // Whether `other` lies completely inside this rectangle where they are placed
+        pub fn contains(&self, other: &Rectangle<T>) -> bool {
+            other.x >= self.x
+                && other.y >= self.y
+                && other.right() <= self.right()
+                && other.bottom() <= self.bottom()
+        }
+
+        // Rectangles that only touch along an edge do not intersect
+        pub fn intersects(&self, other: &Rectangle<T>) -> bool {
+            self.intersection(other).is_some()
+        }
+
+        pub fn intersection(&self, other: &Rectangle<T>) -> Option<Rectangle<T>> {
+            let x = max(self.x, other.x);
+            let y = max(self.y, other.y);
+            let right = min(self.right(), other.right());
+            let bottom = min(self.bottom(), other.bottom());
+
+            if right <= x || bottom <= y {
+                return None;
             }
+
+            Some(Rectangle::new(x, y, right - x, bottom - y))
+        }
+
+        // The smallest rectangle covering both, `None` if its edges or size do not fit into `T`
+        pub fn union(&self, other: &Rectangle<T>) -> Option<Rectangle<T>> {
+            let x = min(self.x, other.x);
+            let y = min(self.y, other.y);
+            let right = max(
+                self.x.checked_add(self.width)?,
+                other.x.checked_add(other.width)?,
+            );
+            let bottom = max(
+                self.y.checked_add(self.height)?,
+                other.y.checked_add(other.height)?,
+            );
+
+            Rectangle::checked_new(x, y, right.checked_sub(x)?, bottom.checked_sub(y)?)
+        }
+
+        // Scales the size, keeping the top left corner in place
+        pub fn scale(&self, factor: T) -> Option<Rectangle<T>> {
+            Rectangle::checked_new(
+                self.x,
+                self.y,
+                self.width.checked_mul(factor)?,
+                self.height.checked_mul(factor)?,
+            )
+        }
+
+        pub fn translate(&self, dx: T, dy: T) -> Option<Rectangle<T>> {
+            Rectangle::checked_new(
+                self.x.checked_add(dx)?,
+                self.y.checked_add(dy)?,
+                self.width,
+                self.height,
+            )
         }
     }
 
-    pub fn get_rect(width: u32, height: u32) -> Rectangle {
-        Rectangle { width, height }
+    // The bounding box of all `rects`, `None` for an empty slice or a box that does not fit
+    pub fn bounding_box<T: Number>(rects: &[Rectangle<T>]) -> Option<Rectangle<T>> {
+        let (first, rest) = rects.split_first()?;
+        rest.iter().try_fold(*first, |acc, rect| acc.union(rect))
     }
 
-    pub fn get_square_rect(size: u32) -> Rectangle {
+    pub fn get_rect<T: Number>(width: T, height: T) -> Rectangle<T> {
+        Rectangle::new(T::zero(), T::zero(), width, height)
+    }
+
+    pub fn get_square_rect<T: Number>(size: T) -> Rectangle<T> {
         Rectangle::square(size)
     }
 
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn checked_and_saturating_area() {
+            let huge = get_rect(u32::MAX, u32::MAX);
+
+            assert_eq!(huge.checked_area(), None);
+            assert_eq!(huge.area(), None);
+            assert_eq!(huge.saturating_area(), u32::MAX);
+            assert_eq!(huge.perimeter(), None);
+
+            let wide = get_rect(u64::from(u32::MAX), u64::from(u32::MAX));
+            assert_eq!(
+                wide.checked_area(),
+                Some(u64::from(u32::MAX) * u64::from(u32::MAX))
+            );
+            assert_eq!(get_rect(30u32, 50).area(), Some(1500));
+            assert_eq!(get_rect(30u32, 50).perimeter(), Some(160));
+        }
+
+        #[test]
+        fn edges_must_fit_into_the_type() {
+            assert_eq!(Rectangle::checked_new(u32::MAX - 10, 0, 20, 1), None);
+            assert_eq!(Rectangle::checked_new(0, i32::MAX, 1, 1), None);
+            assert_eq!(Rectangle::checked_new(f64::MAX, 0.0, f64::MAX, 1.0), None);
+
+            let edge = Rectangle::new(u32::MAX - 10, 0, 10, 1);
+            assert_eq!(edge.right(), u32::MAX);
+            assert_eq!(edge.intersection(&edge), Some(edge));
+            assert!(edge.contains(&edge));
+            assert_eq!(edge.translate(1, 0), None);
+            assert_eq!(edge.scale(2), None);
+        }
+
+        #[test]
+        #[should_panic]
+        fn new_rejects_overflowing_edges() {
+            Rectangle::new(u32::MAX - 10, 0, 20, 1);
+        }
+
+        #[test]
+        fn default_coordinates_are_signed() {
+            let rect: Rectangle = Rectangle::new(-10, -20, 5, 5);
+
+            assert_eq!(rect.x(), -10);
+            assert_eq!(rect.bottom(), -15);
+        }
+
+        #[test]
+        fn float_rectangles() {
+            let sheet = Rectangle::new(0.5, 0.5, 2.0, 1.5);
+
+            assert_eq!(sheet.area(), Some(3.0));
+            assert_eq!(sheet.perimeter(), Some(7.0));
+            assert!(sheet.contains_point(2.4, 1.9));
+            assert!(!sheet.contains_point(2.5, 1.0));
+            assert_eq!(get_rect(f64::MAX, 2.0).checked_area(), None);
+            assert_eq!(get_rect(f64::MAX, 2.0).saturating_area(), f64::MAX);
+        }
+
+        #[test]
+        fn negative_sizes_become_zero() {
+            let rect = Rectangle::new(0, 0, -3, 4);
+
+            assert_eq!(rect.width(), 0);
+            assert_eq!(rect.area(), Some(0));
+        }
+
+        #[test]
+        fn can_hold_and_contains() {
+            let outer = Rectangle::new(0, 0, 10, 10);
+            let inner = Rectangle::new(2, 2, 5, 5);
+            let elsewhere = Rectangle::new(20, 20, 5, 5);
+
+            assert!(outer.can_hold(&inner));
+            assert!(outer.can_hold(&elsewhere));
+            assert!(!inner.can_hold(&outer));
+            assert!(outer.contains(&inner));
+            assert!(outer.contains(&outer));
+            assert!(!outer.contains(&elsewhere));
+        }
+
+        #[test]
+        fn contains_point_excludes_right_and_bottom_edges() {
+            let rect = Rectangle::new(-5, -5, 10, 10);
+
+            assert!(rect.contains_point(-5, -5));
+            assert!(rect.contains_point(4, 4));
+            assert!(!rect.contains_point(5, 0));
+            assert!(!rect.contains_point(0, 5));
+        }
+
+        #[test]
+        fn intersection_and_union() {
+            let a = Rectangle::new(0, 0, 10, 10);
+            let b = Rectangle::new(5, 5, 10, 10);
+            let touching = Rectangle::new(10, 0, 5, 5);
+
+            assert_eq!(a.intersection(&b), Some(Rectangle::new(5, 5, 5, 5)));
+            assert!(a.intersects(&b));
+            assert!(!a.intersects(&touching));
+            assert_eq!(a.union(&b), Some(Rectangle::new(0, 0, 15, 15)));
+            assert_eq!(
+                bounding_box(&[a, b, touching]),
+                Some(Rectangle::new(0, 0, 15, 15))
+            );
+            assert_eq!(bounding_box::<u32>(&[]), None);
+        }
+
+        #[test]
+        fn union_too_large_for_type_is_none() {
+            let left = Rectangle::new(i32::MIN, 0, 1, 1);
+            let right = Rectangle::new(i32::MAX - 1, 0, 1, 1);
+
+            assert_eq!(left.union(&right), None);
+        }
+
+        #[test]
+        fn scale_and_translate_are_checked() {
+            let rect = Rectangle::new(1, 2, 3, 4);
+
+            assert_eq!(rect.scale(2), Some(Rectangle::new(1, 2, 6, 8)));
+            assert_eq!(rect.translate(-1, 3), Some(Rectangle::new(0, 5, 3, 4)));
+            assert_eq!(rect.scale(i32::MAX), None);
+            assert_eq!(rect.translate(i32::MAX, 0), None);
+        }
+    }
 }
@@ -0,0 +1,560 @@
+// Password based sign in for users stored in a `UserRegistry`.
+//
+// Passwords are never stored, only a salted digest: SHA-256 applied `HASH_ROUNDS` times to
+// the salt and password. SHA-256 is implemented below so the crate needs no dependencies.
+// This is a stand-in for a real password hashing function such as Argon2 or bcrypt.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::user_registry::{RegistryError, UserId, UserRegistry};
+use super::user_struct::{User, UserError};
+use crate::closures::{Clock, SystemClock};
+
+const HASH_ROUNDS: u32 = 1_000;
+// Used to hash the password of a sign in for an unknown user
+const UNKNOWN_USER_SALT: [u8; 16] = [0; 16];
+pub const MIN_PASSWORD_LEN: usize = 8;
+pub const DEFAULT_MAX_FAILED_ATTEMPTS: u32 = 3;
+pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthError {
+    // Unknown username or wrong password, deliberately not telling which
+    InvalidCredentials,
+    Locked,
+    Inactive,
+    WeakPassword,
+    InvalidSession,
+    SessionExpired,
+    Registry(RegistryError),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuthError::InvalidCredentials => write!(f, "invalid username or password"),
+            AuthError::Locked => write!(f, "account is locked after too many failed sign ins"),
+            AuthError::Inactive => write!(f, "account is not active"),
+            AuthError::WeakPassword => write!(
+                f,
+                "password must be at least {} characters long",
+                MIN_PASSWORD_LEN
+            ),
+            AuthError::InvalidSession => write!(f, "unknown session token"),
+            AuthError::SessionExpired => write!(f, "session has expired"),
+            AuthError::Registry(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<RegistryError> for AuthError {
+    fn from(e: RegistryError) -> AuthError {
+        match e {
+            RegistryError::User(UserError::Inactive) => AuthError::Inactive,
+            e => AuthError::Registry(e),
+        }
+    }
+}
+
+struct Credentials {
+    salt: [u8; 16],
+    digest: [u8; 32],
+    failed_attempts: u32,
+    locked: bool,
+}
+
+fn hash_password(salt: &[u8], password: &str) -> [u8; 32] {
+    let mut input = Vec::with_capacity(32 + salt.len() + password.len());
+    input.extend_from_slice(salt);
+    input.extend_from_slice(password.as_bytes());
+    let mut digest = sha256(&input);
+
+    for _ in 1..HASH_ROUNDS {
+        input.clear();
+        input.extend_from_slice(&digest);
+        input.extend_from_slice(salt);
+        input.extend_from_slice(password.as_bytes());
+        digest = sha256(&input);
+    }
+
+    digest
+}
+
+// Compares every byte so the time taken does not reveal where the digests differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub token: String,
+    pub user_id: UserId,
+    // Time according to the authenticator's clock after which the token stops working
+    pub expires_at: Duration,
+}
+
+pub struct Authenticator {
+    registry: UserRegistry,
+    credentials: HashMap<UserId, Credentials>,
+    sessions: HashMap<String, Session>,
+    clock: Box<dyn Clock>,
+    session_ttl: Duration,
+    max_failed_attempts: u32,
+    // Mixed into salts and tokens so two authenticators never produce the same ones
+    seed: [u8; 32],
+    counter: u64,
+}
+
+impl Authenticator {
+    pub fn new(registry: UserRegistry) -> Authenticator {
+        Authenticator::with_clock(registry, Box::new(SystemClock::new()))
+    }
+
+    pub fn with_clock(registry: UserRegistry, clock: Box<dyn Clock>) -> Authenticator {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let address = &registry as *const UserRegistry as usize;
+
+        Authenticator {
+            registry,
+            credentials: HashMap::new(),
+            sessions: HashMap::new(),
+            clock,
+            session_ttl: DEFAULT_SESSION_TTL,
+            max_failed_attempts: DEFAULT_MAX_FAILED_ATTEMPTS,
+            seed: sha256(format!("{}:{}", nanos, address).as_bytes()),
+            counter: 0,
+        }
+    }
+
+    pub fn set_session_ttl(&mut self, ttl: Duration) {
+        self.session_ttl = ttl;
+    }
+
+    pub fn set_max_failed_attempts(&mut self, attempts: u32) {
+        self.max_failed_attempts = attempts.max(1);
+    }
+
+    pub fn registry(&self) -> &UserRegistry {
+        &self.registry
+    }
+
+    // Sessions of users deactivated or removed through here stop validating, but they are only
+    // dropped by `deactivate` and `remove` below
+    pub fn registry_mut(&mut self) -> &mut UserRegistry {
+        &mut self.registry
+    }
+
+    // Deactivates the user and ends all of their sessions
+    pub fn deactivate(&mut self, id: UserId) -> Result<(), AuthError> {
+        self.registry.deactivate(id)?;
+        self.end_sessions(id);
+        Ok(())
+    }
+
+    // Removes the user together with their password and sessions
+    pub fn remove(&mut self, id: UserId) -> Result<User, AuthError> {
+        let user = self.registry.remove(id)?;
+        self.credentials.remove(&id);
+        self.end_sessions(id);
+        Ok(user)
+    }
+
+    // Sets or replaces the password, which also unlocks the account
+    pub fn set_password(&mut self, id: UserId, password: &str) -> Result<(), AuthError> {
+        if self.registry.get(id).is_none() {
+            return Err(RegistryError::NotFound(id).into());
+        }
+        if password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(AuthError::WeakPassword);
+        }
+
+        let mut salt = [0; 16];
+        salt.copy_from_slice(&self.next_random()[..16]);
+        let digest = hash_password(&salt, password);
+
+        self.credentials.insert(
+            id,
+            Credentials {
+                salt,
+                digest,
+                failed_attempts: 0,
+                locked: false,
+            },
+        );
+        Ok(())
+    }
+
+    // Checks the password, counts the sign in and hands out a session token. After
+    // `max_failed_attempts` wrong passwords in a row the account is locked.
+    pub fn sign_in(&mut self, username: &str, password: &str) -> Result<Session, AuthError> {
+        let found = match self.registry.find_by_username(username) {
+            Some((id, _)) => self
+                .credentials
+                .get_mut(&id)
+                .map(|credentials| (id, credentials)),
+            None => None,
+        };
+        let (id, credentials) = match found {
+            Some(found) => found,
+            None => {
+                // Hash anyway so an unknown user takes as long to reject as a wrong password
+                let digest = hash_password(&UNKNOWN_USER_SALT, password);
+                constant_time_eq(&digest, &[0; 32]);
+                return Err(AuthError::InvalidCredentials);
+            }
+        };
+
+        // Hash before looking at the lock so a locked account is not rejected any faster
+        let digest = hash_password(&credentials.salt, password);
+        let matches = constant_time_eq(&digest, &credentials.digest);
+        if credentials.locked {
+            return Err(AuthError::Locked);
+        }
+
+        if !matches {
+            credentials.failed_attempts += 1;
+            if credentials.failed_attempts >= self.max_failed_attempts {
+                credentials.locked = true;
+                return Err(AuthError::Locked);
+            }
+            return Err(AuthError::InvalidCredentials);
+        }
+
+        credentials.failed_attempts = 0;
+        self.registry.record_sign_in(id)?;
+
+        let session = Session {
+            token: to_hex(&self.next_random()),
+            user_id: id,
+            // A ttl too long to add to the clock means the session never expires
+            expires_at: self
+                .clock
+                .now()
+                .checked_add(self.session_ttl)
+                .unwrap_or(Duration::MAX),
+        };
+        self.sessions.insert(session.token.clone(), session.clone());
+        Ok(session)
+    }
+
+    // Returns the user the token belongs to
+    pub fn validate(&mut self, token: &str) -> Result<UserId, AuthError> {
+        let session = self.sessions.get(token).ok_or(AuthError::InvalidSession)?;
+
+        if self.clock.now() >= session.expires_at {
+            self.sessions.remove(token);
+            return Err(AuthError::SessionExpired);
+        }
+
+        // The user may have been removed or deactivated since signing in
+        let id = session.user_id;
+        let error = match self.registry.get(id) {
+            Some(user) if user.is_active() => return Ok(id),
+            Some(_) => AuthError::Inactive,
+            None => AuthError::InvalidSession,
+        };
+        self.end_sessions(id);
+        Err(error)
+    }
+
+    // Returns false if the token was not a known session
+    pub fn sign_out(&mut self, token: &str) -> bool {
+        self.sessions.remove(token).is_some()
+    }
+
+    pub fn is_locked(&self, id: UserId) -> bool {
+        self.credentials.get(&id).is_some_and(|c| c.locked)
+    }
+
+    pub fn unlock(&mut self, id: UserId) {
+        if let Some(credentials) = self.credentials.get_mut(&id) {
+            credentials.locked = false;
+            credentials.failed_attempts = 0;
+        }
+    }
+
+    fn end_sessions(&mut self, id: UserId) {
+        self.sessions.retain(|_, session| session.user_id != id);
+    }
+
+    fn next_random(&mut self) -> [u8; 32] {
+        self.counter += 1;
+
+        let mut input = self.seed.to_vec();
+        input.extend_from_slice(&self.counter.to_be_bytes());
+        input.extend_from_slice(&self.clock.now().as_nanos().to_be_bytes());
+        sha256(&input)
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// SHA-256 as specified in FIPS 180-4
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    // Pad with a single 1 bit, zeros and the message length in bits
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let temp1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(maj);
+
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e, f, g, hh].iter()) {
+            *state = state.wrapping_add(*value);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (chunk, word) in digest.chunks_mut(4).zip(h.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct FakeClock {
+        now: Rc<Cell<Duration>>,
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Duration {
+            self.now.get()
+        }
+    }
+
+    fn authenticator() -> (Authenticator, UserId, Rc<Cell<Duration>>) {
+        let mut registry = UserRegistry::new();
+        let id = registry
+            .register(String::from("ameer"), String::from("ameer@example.com"))
+            .unwrap();
+        registry.activate(id).unwrap();
+
+        let now = Rc::new(Cell::new(Duration::from_secs(0)));
+        let clock = FakeClock {
+            now: Rc::clone(&now),
+        };
+        let mut auth = Authenticator::with_clock(registry, Box::new(clock));
+        auth.set_password(id, "correct horse").unwrap();
+
+        (auth, id, now)
+    }
+
+    #[test]
+    fn sha256_matches_known_digests() {
+        assert_eq!(
+            to_hex(&sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            to_hex(&sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            to_hex(&sha256(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn same_password_gets_different_digests() {
+        let (mut auth, id, _) = authenticator();
+        let other = auth
+            .registry_mut()
+            .register(String::from("hamza"), String::from("hamza@example.com"))
+            .unwrap();
+        auth.set_password(other, "correct horse").unwrap();
+
+        assert_ne!(auth.credentials[&id].salt, auth.credentials[&other].salt);
+        assert_ne!(
+            auth.credentials[&id].digest,
+            auth.credentials[&other].digest
+        );
+    }
+
+    #[test]
+    fn sign_in_issues_session_and_counts_sign_ins() {
+        let (mut auth, id, now) = authenticator();
+
+        let session = auth.sign_in("ameer", "correct horse").unwrap();
+        assert_eq!(session.user_id, id);
+        assert_eq!(auth.validate(&session.token), Ok(id));
+        assert_eq!(auth.registry().get(id).unwrap().sign_in_count(), 1);
+
+        now.set(DEFAULT_SESSION_TTL);
+        assert_eq!(
+            auth.validate(&session.token),
+            Err(AuthError::SessionExpired)
+        );
+        assert_eq!(
+            auth.validate(&session.token),
+            Err(AuthError::InvalidSession)
+        );
+
+        let second = auth.sign_in("ameer", "correct horse").unwrap();
+        assert_ne!(second.token, session.token);
+        assert_eq!(auth.registry().get(id).unwrap().sign_in_count(), 2);
+        assert!(auth.sign_out(&second.token));
+        assert_eq!(auth.validate(&second.token), Err(AuthError::InvalidSession));
+    }
+
+    #[test]
+    fn wrong_credentials_are_rejected() {
+        let (mut auth, id, _) = authenticator();
+
+        assert_eq!(
+            auth.sign_in("ameer", "wrong horse"),
+            Err(AuthError::InvalidCredentials)
+        );
+        assert_eq!(
+            auth.sign_in("nobody", "correct horse"),
+            Err(AuthError::InvalidCredentials)
+        );
+        assert_eq!(auth.set_password(id, "short"), Err(AuthError::WeakPassword));
+
+        auth.registry_mut().deactivate(id).unwrap();
+        assert_eq!(
+            auth.sign_in("ameer", "correct horse"),
+            Err(AuthError::Inactive)
+        );
+    }
+
+    #[test]
+    fn sessions_end_when_the_user_is_deactivated_or_removed() {
+        let (mut auth, id, _) = authenticator();
+
+        let session = auth.sign_in("ameer", "correct horse").unwrap();
+        auth.registry_mut().deactivate(id).unwrap();
+        assert_eq!(auth.validate(&session.token), Err(AuthError::Inactive));
+        auth.registry_mut().activate(id).unwrap();
+        assert_eq!(
+            auth.validate(&session.token),
+            Err(AuthError::InvalidSession)
+        );
+
+        let session = auth.sign_in("ameer", "correct horse").unwrap();
+        auth.deactivate(id).unwrap();
+        auth.registry_mut().activate(id).unwrap();
+        assert_eq!(
+            auth.validate(&session.token),
+            Err(AuthError::InvalidSession)
+        );
+
+        let session = auth.sign_in("ameer", "correct horse").unwrap();
+        assert_eq!(auth.remove(id).unwrap().username(), "ameer");
+        assert_eq!(
+            auth.validate(&session.token),
+            Err(AuthError::InvalidSession)
+        );
+        assert_eq!(
+            auth.sign_in("ameer", "correct horse"),
+            Err(AuthError::InvalidCredentials)
+        );
+    }
+
+    #[test]
+    fn very_long_session_ttls_do_not_overflow() {
+        let (mut auth, id, now) = authenticator();
+        now.set(Duration::from_secs(10));
+        auth.set_session_ttl(Duration::MAX);
+
+        let session = auth.sign_in("ameer", "correct horse").unwrap();
+        assert_eq!(session.expires_at, Duration::MAX);
+        now.set(Duration::from_secs(10 * 365 * 24 * 60 * 60));
+        assert_eq!(auth.validate(&session.token), Ok(id));
+    }
+
+    #[test]
+    fn account_locks_after_repeated_failures() {
+        let (mut auth, id, _) = authenticator();
+
+        assert_eq!(
+            auth.sign_in("ameer", "one"),
+            Err(AuthError::InvalidCredentials)
+        );
+        // A successful sign in resets the counter
+        auth.sign_in("ameer", "correct horse").unwrap();
+
+        for _ in 0..DEFAULT_MAX_FAILED_ATTEMPTS - 1 {
+            assert_eq!(
+                auth.sign_in("ameer", "nope"),
+                Err(AuthError::InvalidCredentials)
+            );
+        }
+        assert_eq!(auth.sign_in("ameer", "nope"), Err(AuthError::Locked));
+        assert!(auth.is_locked(id));
+        assert_eq!(
+            auth.sign_in("ameer", "correct horse"),
+            Err(AuthError::Locked)
+        );
+
+        auth.unlock(id);
+        assert!(auth.sign_in("ameer", "correct horse").is_ok());
+    }
+}
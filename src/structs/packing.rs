@@ -0,0 +1,375 @@
+// Packs rectangles into a fixed size container, e.g. sprites into a texture atlas.
+// Only the sizes of the input rectangles matter; the result places each of them at a position
+// inside the container. Rectangles are never rotated.
+//
+// Two algorithms are available:
+// - `Shelf` fills rows ("shelves") left to right, tallest rectangles first. Fast and simple.
+// - `MaxRects` keeps the list of maximal free areas and puts every rectangle where it leaves
+//   the shortest leftover side ("best short side fit"). Slower but packs much tighter.
+
+use std::fmt;
+
+use super::rectangle::Rectangle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Shelf,
+    MaxRects,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    // Position of the rectangle in the input slice
+    pub index: usize,
+    pub rect: Rectangle<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packing {
+    // Sorted by `index`
+    pub placements: Vec<Placement>,
+    // Share of the container area that is covered, between 0 and 1
+    pub utilization: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PackingError {
+    // The rectangle is larger than the container itself
+    TooLarge {
+        index: usize,
+        width: u32,
+        height: u32,
+    },
+    // The rectangle fits the empty container but there is no room left for it
+    OutOfSpace {
+        index: usize,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl fmt::Display for PackingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackingError::TooLarge {
+                index,
+                width,
+                height,
+            } => write!(
+                f,
+                "rectangle {} ({}x{}) is larger than the container",
+                index, width, height
+            ),
+            PackingError::OutOfSpace {
+                index,
+                width,
+                height,
+            } => write!(
+                f,
+                "no room left in the container for rectangle {} ({}x{})",
+                index, width, height
+            ),
+        }
+    }
+}
+
+pub fn pack(
+    container_width: u32,
+    container_height: u32,
+    rects: &[Rectangle<u32>],
+    algorithm: Algorithm,
+) -> Result<Packing, PackingError> {
+    for (index, rect) in rects.iter().enumerate() {
+        if rect.width() > container_width || rect.height() > container_height {
+            return Err(PackingError::TooLarge {
+                index,
+                width: rect.width(),
+                height: rect.height(),
+            });
+        }
+    }
+
+    let mut placements = match algorithm {
+        Algorithm::Shelf => pack_shelves(container_width, container_height, rects)?,
+        Algorithm::MaxRects => pack_max_rects(container_width, container_height, rects)?,
+    };
+    placements.sort_by_key(|placement| placement.index);
+
+    let used: u64 = rects
+        .iter()
+        .map(|rect| u64::from(rect.width()) * u64::from(rect.height()))
+        .sum();
+    let total = u64::from(container_width) * u64::from(container_height);
+    let utilization = if total == 0 {
+        0.0
+    } else {
+        used as f64 / total as f64
+    };
+
+    Ok(Packing {
+        placements,
+        utilization,
+    })
+}
+
+fn out_of_space(index: usize, rect: &Rectangle<u32>) -> PackingError {
+    PackingError::OutOfSpace {
+        index,
+        width: rect.width(),
+        height: rect.height(),
+    }
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    used_width: u32,
+}
+
+fn pack_shelves(
+    container_width: u32,
+    container_height: u32,
+    rects: &[Rectangle<u32>],
+) -> Result<Vec<Placement>, PackingError> {
+    let mut order: Vec<usize> = (0..rects.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(rects[i].height()));
+
+    let mut shelves: Vec<Shelf> = Vec::new();
+    let mut placements = Vec::new();
+
+    for index in order {
+        let rect = &rects[index];
+        let (width, height) = (rect.width(), rect.height());
+
+        let shelf = match shelves
+            .iter_mut()
+            .find(|shelf| shelf.height >= height && container_width - shelf.used_width >= width)
+        {
+            Some(shelf) => shelf,
+            None => {
+                let y = shelves.last().map_or(0, |shelf| shelf.y + shelf.height);
+                if container_height - y < height {
+                    return Err(out_of_space(index, rect));
+                }
+                shelves.push(Shelf {
+                    y,
+                    height,
+                    used_width: 0,
+                });
+                shelves.last_mut().unwrap()
+            }
+        };
+
+        placements.push(Placement {
+            index,
+            rect: Rectangle::new(shelf.used_width, shelf.y, width, height),
+        });
+        shelf.used_width += width;
+    }
+
+    Ok(placements)
+}
+
+fn pack_max_rects(
+    container_width: u32,
+    container_height: u32,
+    rects: &[Rectangle<u32>],
+) -> Result<Vec<Placement>, PackingError> {
+    let mut order: Vec<usize> = (0..rects.len()).collect();
+    order.sort_by_key(|&i| {
+        std::cmp::Reverse(u64::from(rects[i].width()) * u64::from(rects[i].height()))
+    });
+
+    let mut free = vec![Rectangle::new(0, 0, container_width, container_height)];
+    let mut placements = Vec::new();
+
+    for index in order {
+        let rect = &rects[index];
+        let (width, height) = (rect.width(), rect.height());
+
+        if width == 0 || height == 0 {
+            placements.push(Placement {
+                index,
+                rect: Rectangle::new(0, 0, width, height),
+            });
+            continue;
+        }
+
+        // Best short side fit, ties broken by the long side
+        let best = free
+            .iter()
+            .filter(|area| area.width() >= width && area.height() >= height)
+            .min_by_key(|area| {
+                let dw = area.width() - width;
+                let dh = area.height() - height;
+                (dw.min(dh), dw.max(dh))
+            })
+            .copied()
+            .ok_or_else(|| out_of_space(index, rect))?;
+
+        let placed = Rectangle::new(best.x(), best.y(), width, height);
+        free = split_free_areas(&free, &placed);
+        placements.push(Placement {
+            index,
+            rect: placed,
+        });
+    }
+
+    Ok(placements)
+}
+
+// Cuts `placed` out of every free area it overlaps, keeping the maximal leftover areas
+fn split_free_areas(free: &[Rectangle<u32>], placed: &Rectangle<u32>) -> Vec<Rectangle<u32>> {
+    let mut result = Vec::new();
+
+    for area in free {
+        if !area.intersects(placed) {
+            result.push(*area);
+            continue;
+        }
+
+        if placed.x() > area.x() {
+            result.push(Rectangle::new(
+                area.x(),
+                area.y(),
+                placed.x() - area.x(),
+                area.height(),
+            ));
+        }
+        if placed.right() < area.right() {
+            result.push(Rectangle::new(
+                placed.right(),
+                area.y(),
+                area.right() - placed.right(),
+                area.height(),
+            ));
+        }
+        if placed.y() > area.y() {
+            result.push(Rectangle::new(
+                area.x(),
+                area.y(),
+                area.width(),
+                placed.y() - area.y(),
+            ));
+        }
+        if placed.bottom() < area.bottom() {
+            result.push(Rectangle::new(
+                area.x(),
+                placed.bottom(),
+                area.width(),
+                area.bottom() - placed.bottom(),
+            ));
+        }
+    }
+
+    // Drop areas that are completely covered by another one
+    let mut pruned: Vec<Rectangle<u32>> = Vec::new();
+    for (i, area) in result.iter().enumerate() {
+        let covered = result
+            .iter()
+            .enumerate()
+            .any(|(j, other)| i != j && other.contains(area) && (other != area || j < i));
+        if !covered {
+            pruned.push(*area);
+        }
+    }
+    pruned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::rectangle::get_rect;
+
+    fn assert_valid(packing: &Packing, rects: &[Rectangle<u32>], width: u32, height: u32) {
+        let container = Rectangle::new(0, 0, width, height);
+
+        assert_eq!(packing.placements.len(), rects.len());
+        for (i, placement) in packing.placements.iter().enumerate() {
+            assert_eq!(placement.index, i);
+            assert_eq!(placement.rect.width(), rects[i].width());
+            assert_eq!(placement.rect.height(), rects[i].height());
+            assert!(container.contains(&placement.rect));
+
+            for other in &packing.placements[i + 1..] {
+                assert!(
+                    !placement.rect.intersects(&other.rect),
+                    "{:?} overlaps {:?}",
+                    placement,
+                    other
+                );
+            }
+        }
+    }
+
+    fn sprites() -> Vec<Rectangle<u32>> {
+        vec![
+            get_rect(40, 40),
+            get_rect(60, 20),
+            get_rect(20, 60),
+            get_rect(30, 30),
+            get_rect(50, 10),
+            get_rect(10, 50),
+            get_rect(25, 25),
+        ]
+    }
+
+    #[test]
+    fn both_algorithms_produce_valid_packings() {
+        let rects = sprites();
+
+        for algorithm in [Algorithm::Shelf, Algorithm::MaxRects].iter() {
+            let packing = pack(100, 100, &rects, *algorithm).unwrap();
+            assert_valid(&packing, &rects, 100, 100);
+            assert!((packing.utilization - 0.6525).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn max_rects_fills_a_container_exactly() {
+        let rects = vec![
+            get_rect(50, 100),
+            get_rect(50, 50),
+            get_rect(25, 50),
+            get_rect(25, 50),
+        ];
+
+        let packing = pack(100, 100, &rects, Algorithm::MaxRects).unwrap();
+
+        assert_valid(&packing, &rects, 100, 100);
+        assert_eq!(packing.utilization, 1.0);
+    }
+
+    #[test]
+    fn shelf_places_rows_tallest_first() {
+        let rects = vec![get_rect(30, 10), get_rect(30, 20), get_rect(50, 15)];
+
+        let packing = pack(60, 40, &rects, Algorithm::Shelf).unwrap();
+
+        assert_eq!(packing.placements[1].rect, Rectangle::new(0, 0, 30, 20));
+        assert_eq!(packing.placements[2].rect, Rectangle::new(0, 20, 50, 15));
+        assert_eq!(packing.placements[0].rect, Rectangle::new(30, 0, 30, 10));
+    }
+
+    #[test]
+    fn rectangles_that_cannot_fit_are_rejected() {
+        let too_large = vec![get_rect(10, 10), get_rect(101, 5)];
+        assert_eq!(
+            pack(100, 100, &too_large, Algorithm::Shelf),
+            Err(PackingError::TooLarge {
+                index: 1,
+                width: 101,
+                height: 5
+            })
+        );
+
+        let too_many = vec![get_rect(60, 60), get_rect(60, 60)];
+        for algorithm in [Algorithm::Shelf, Algorithm::MaxRects].iter() {
+            let error = pack(100, 100, &too_many, *algorithm).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "no room left in the container for rectangle 1 (60x60)"
+            );
+        }
+    }
+}
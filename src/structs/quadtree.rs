@@ -0,0 +1,405 @@
+// A quadtree spatial index over positioned rectangles. Every node covers a part of the indexed
+// area and splits into four quadrants once it holds too many rectangles. A rectangle is stored
+// in the smallest node that contains it completely, so rectangles crossing a quadrant border
+// stay in the parent node.
+//
+// Region queries and nearest neighbour searches only visit the nodes that can hold a match,
+// which is much faster than scanning every rectangle once there are thousands of them.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::rectangle::Rectangle;
+
+// A node splits once it holds more rectangles than this
+const MAX_ITEMS: usize = 8;
+const MAX_DEPTH: usize = 12;
+
+pub type ItemId = u64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuadTreeError {
+    // The rectangle is not completely inside the area covered by the tree
+    OutOfBounds(Rectangle<u32>),
+}
+
+impl fmt::Display for QuadTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuadTreeError::OutOfBounds(rect) => write!(
+                f,
+                "rectangle at ({}, {}) with size {}x{} is outside the indexed area",
+                rect.x(),
+                rect.y(),
+                rect.width(),
+                rect.height()
+            ),
+        }
+    }
+}
+
+pub struct QuadTree {
+    root: Node,
+    // Where every item is, so `remove` can walk straight to its node
+    index: HashMap<ItemId, Rectangle<u32>>,
+    next_id: ItemId,
+}
+
+impl QuadTree {
+    pub fn new(bounds: Rectangle<u32>) -> QuadTree {
+        QuadTree {
+            root: Node::new(bounds, 0),
+            index: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn insert(&mut self, rect: Rectangle<u32>) -> Result<ItemId, QuadTreeError> {
+        if !self.root.bounds.contains(&rect) {
+            return Err(QuadTreeError::OutOfBounds(rect));
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.root.insert(id, rect);
+        self.index.insert(id, rect);
+        Ok(id)
+    }
+
+    pub fn remove(&mut self, id: ItemId) -> Option<Rectangle<u32>> {
+        let rect = self.index.remove(&id)?;
+        self.root.remove(id, &rect);
+        Some(rect)
+    }
+
+    pub fn get(&self, id: ItemId) -> Option<&Rectangle<u32>> {
+        self.index.get(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    // Ids of all rectangles overlapping `region`, sorted. Rectangles that only touch its edge
+    // do not overlap it.
+    pub fn query(&self, region: &Rectangle<u32>) -> Vec<ItemId> {
+        let mut found = Vec::new();
+        self.root.query(region, &mut found);
+        found.sort_unstable();
+        found
+    }
+
+    // The rectangle closest to the point, rectangles containing the point are at distance 0.
+    // Ties go to the lowest id.
+    pub fn nearest(&self, x: u32, y: u32) -> Option<ItemId> {
+        let mut best = None;
+        self.root.nearest(x, y, &mut best);
+        best.map(|(id, _)| id)
+    }
+}
+
+struct Node {
+    bounds: Rectangle<u32>,
+    depth: usize,
+    items: Vec<(ItemId, Rectangle<u32>)>,
+    // Either empty or the four quadrants
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(bounds: Rectangle<u32>, depth: usize) -> Node {
+        Node {
+            bounds,
+            depth,
+            items: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, id: ItemId, rect: Rectangle<u32>) {
+        if let Some(child) = self.child_containing(&rect) {
+            child.insert(id, rect);
+            return;
+        }
+
+        self.items.push((id, rect));
+
+        if self.children.is_empty()
+            && self.items.len() > MAX_ITEMS
+            && self.depth < MAX_DEPTH
+            && self.bounds.width() >= 2
+            && self.bounds.height() >= 2
+        {
+            self.split();
+        }
+    }
+
+    fn split(&mut self) {
+        let (x, y) = (self.bounds.x(), self.bounds.y());
+        let (width, height) = (self.bounds.width(), self.bounds.height());
+        let (half_width, half_height) = (width / 2, height / 2);
+        let depth = self.depth + 1;
+
+        self.children = vec![
+            Node::new(Rectangle::new(x, y, half_width, half_height), depth),
+            Node::new(
+                Rectangle::new(x + half_width, y, width - half_width, half_height),
+                depth,
+            ),
+            Node::new(
+                Rectangle::new(x, y + half_height, half_width, height - half_height),
+                depth,
+            ),
+            Node::new(
+                Rectangle::new(
+                    x + half_width,
+                    y + half_height,
+                    width - half_width,
+                    height - half_height,
+                ),
+                depth,
+            ),
+        ];
+
+        for (id, rect) in std::mem::take(&mut self.items) {
+            self.insert(id, rect);
+        }
+    }
+
+    // Insert and remove both pick the first matching quadrant, so they always take the same path
+    fn child_containing(&mut self, rect: &Rectangle<u32>) -> Option<&mut Node> {
+        self.children
+            .iter_mut()
+            .find(|child| child.bounds.contains(rect))
+    }
+
+    fn remove(&mut self, id: ItemId, rect: &Rectangle<u32>) -> bool {
+        if let Some(position) = self.items.iter().position(|(item, _)| *item == id) {
+            self.items.swap_remove(position);
+            return true;
+        }
+
+        match self.child_containing(rect) {
+            Some(child) => child.remove(id, rect),
+            None => false,
+        }
+    }
+
+    fn query(&self, region: &Rectangle<u32>, found: &mut Vec<ItemId>) {
+        found.extend(
+            self.items
+                .iter()
+                .filter(|(_, rect)| rect.intersects(region))
+                .map(|(id, _)| *id),
+        );
+
+        for child in &self.children {
+            if child.bounds.intersects(region) {
+                child.query(region, found);
+            }
+        }
+    }
+
+    fn nearest(&self, x: u32, y: u32, best: &mut Option<(ItemId, u64)>) {
+        for (id, rect) in &self.items {
+            let distance = distance_squared(rect, x, y);
+            if best
+                .is_none_or(|(best_id, best_distance)| (distance, *id) < (best_distance, best_id))
+            {
+                *best = Some((*id, distance));
+            }
+        }
+
+        // Closest quadrants first, so the others can usually be skipped
+        let mut children: Vec<(u64, &Node)> = self
+            .children
+            .iter()
+            .map(|child| (distance_squared(&child.bounds, x, y), child))
+            .collect();
+        children.sort_by_key(|(distance, _)| *distance);
+
+        for (distance, child) in children {
+            if best.is_none_or(|(_, best_distance)| distance <= best_distance) {
+                child.nearest(x, y, best);
+            }
+        }
+    }
+}
+
+// Squared distance from the point to the closest point of the rectangle
+fn distance_squared(rect: &Rectangle<u32>, x: u32, y: u32) -> u64 {
+    let axis = |value: u32, start: u32, end: u32| -> u64 {
+        if value < start {
+            u64::from(start - value)
+        } else if value > end {
+            u64::from(value - end)
+        } else {
+            0
+        }
+    };
+
+    let dx = axis(x, rect.x(), rect.right());
+    let dy = axis(y, rect.y(), rect.bottom());
+    dx * dx + dy * dy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::closures::rng::Rng;
+    use std::time::Instant;
+
+    fn random_rects(rng: &mut Rng, count: usize) -> Vec<Rectangle<u32>> {
+        (0..count)
+            .map(|_| {
+                let x = (rng.next_u64() % 1_000) as u32;
+                let y = (rng.next_u64() % 1_000) as u32;
+                let width = 1 + (rng.next_u64() % 24) as u32;
+                let height = 1 + (rng.next_u64() % 24) as u32;
+                Rectangle::new(x, y, width, height)
+            })
+            .collect()
+    }
+
+    fn linear_nearest(items: &[(ItemId, Rectangle<u32>)], x: u32, y: u32) -> Option<ItemId> {
+        items
+            .iter()
+            .min_by_key(|(id, rect)| (distance_squared(rect, x, y), *id))
+            .map(|(id, _)| *id)
+    }
+
+    #[test]
+    fn queries_match_a_linear_scan() {
+        let mut rng = Rng::seeded(11);
+        let mut tree = QuadTree::new(Rectangle::new(0, 0, 1_024, 1_024));
+        let mut items: Vec<(ItemId, Rectangle<u32>)> = random_rects(&mut rng, 2_000)
+            .into_iter()
+            .map(|rect| (tree.insert(rect).unwrap(), rect))
+            .collect();
+
+        // Remove every third rectangle to exercise removal as well
+        for (id, rect) in items.iter().step_by(3) {
+            assert_eq!(tree.remove(*id), Some(*rect));
+        }
+        items = items
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| i % 3 != 0)
+            .map(|(_, item)| item)
+            .collect();
+        assert_eq!(tree.len(), items.len());
+
+        for region in random_rects(&mut rng, 100) {
+            let region = region.scale(4).unwrap();
+            let expected: Vec<ItemId> = items
+                .iter()
+                .filter(|(_, rect)| rect.intersects(&region))
+                .map(|(id, _)| *id)
+                .collect();
+            assert_eq!(tree.query(&region), expected);
+        }
+
+        for _ in 0..100 {
+            let x = (rng.next_u64() % 1_024) as u32;
+            let y = (rng.next_u64() % 1_024) as u32;
+            assert_eq!(tree.nearest(x, y), linear_nearest(&items, x, y));
+        }
+    }
+
+    // Timing comparison, run with `cargo test -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn quadtree_is_faster_than_a_linear_scan() {
+        let mut rng = Rng::seeded(2020);
+        let mut tree = QuadTree::new(Rectangle::new(0, 0, 10_000, 10_000));
+        let items: Vec<(ItemId, Rectangle<u32>)> = (0..20_000)
+            .map(|_| {
+                let rect = Rectangle::new(
+                    (rng.next_u64() % 9_950) as u32,
+                    (rng.next_u64() % 9_950) as u32,
+                    1 + (rng.next_u64() % 50) as u32,
+                    1 + (rng.next_u64() % 50) as u32,
+                );
+                (tree.insert(rect).unwrap(), rect)
+            })
+            .collect();
+        let regions: Vec<Rectangle<u32>> = (0..1_000)
+            .map(|_| {
+                let x = (rng.next_u64() % 9_800) as u32;
+                let y = (rng.next_u64() % 9_800) as u32;
+                Rectangle::new(x, y, 200, 200)
+            })
+            .collect();
+
+        let start = Instant::now();
+        let tree_hits: Vec<Vec<ItemId>> = regions.iter().map(|region| tree.query(region)).collect();
+        let tree_nearest: Vec<Option<ItemId>> = regions
+            .iter()
+            .map(|region| tree.nearest(region.x(), region.y()))
+            .collect();
+        let tree_time = start.elapsed();
+
+        let start = Instant::now();
+        let scan_hits: Vec<Vec<ItemId>> = regions
+            .iter()
+            .map(|region| {
+                items
+                    .iter()
+                    .filter(|(_, rect)| rect.intersects(region))
+                    .map(|(id, _)| *id)
+                    .collect()
+            })
+            .collect();
+        let scan_nearest: Vec<Option<ItemId>> = regions
+            .iter()
+            .map(|region| linear_nearest(&items, region.x(), region.y()))
+            .collect();
+        let scan_time = start.elapsed();
+
+        println!(
+            "{} region and nearest neighbour queries over {} rectangles took {:?} with the quadtree and {:?} with a linear scan",
+            regions.len(),
+            items.len(),
+            tree_time,
+            scan_time
+        );
+        assert_eq!(tree_hits, scan_hits);
+        assert_eq!(tree_nearest, scan_nearest);
+        assert!(tree_time < scan_time);
+    }
+
+    #[test]
+    fn rectangles_outside_the_bounds_are_rejected() {
+        let mut tree = QuadTree::new(Rectangle::new(0, 0, 100, 100));
+
+        assert_eq!(
+            tree.insert(Rectangle::new(90, 90, 20, 5)),
+            Err(QuadTreeError::OutOfBounds(Rectangle::new(90, 90, 20, 5)))
+        );
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn removed_rectangles_are_no_longer_found() {
+        let mut tree = QuadTree::new(Rectangle::new(0, 0, 100, 100));
+        let a = tree.insert(Rectangle::new(10, 10, 5, 5)).unwrap();
+        let b = tree.insert(Rectangle::new(50, 50, 5, 5)).unwrap();
+
+        assert_eq!(tree.nearest(12, 12), Some(a));
+        assert_eq!(tree.remove(a), Some(Rectangle::new(10, 10, 5, 5)));
+        assert_eq!(tree.remove(a), None);
+
+        assert_eq!(tree.nearest(12, 12), Some(b));
+        assert!(tree.query(&Rectangle::new(0, 0, 20, 20)).is_empty());
+        assert_eq!(tree.get(b), Some(&Rectangle::new(50, 50, 5, 5)));
+
+        tree.remove(b);
+        assert_eq!(tree.nearest(12, 12), None);
+    }
+}
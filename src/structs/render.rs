@@ -0,0 +1,212 @@
+// Draws rectangles so layouts can be inspected by eye, either as ASCII art for the terminal or
+// as an SVG document. A scene has a fixed size starting at (0, 0); anything outside of it is
+// cut off.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::rectangle::Rectangle;
+
+// Fill colors used for the rectangles in SVG output, in order
+const PALETTE: [&str; 6] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    width: u32,
+    height: u32,
+    rects: Vec<Rectangle<u32>>,
+}
+
+impl Scene {
+    pub fn new(width: u32, height: u32) -> Scene {
+        Scene {
+            width,
+            height,
+            rects: Vec::new(),
+        }
+    }
+
+    // A scene just large enough to show all the rectangles
+    pub fn fit(rects: &[Rectangle<u32>]) -> Scene {
+        let width = rects.iter().map(|rect| rect.right()).max().unwrap_or(0);
+        let height = rects.iter().map(|rect| rect.bottom()).max().unwrap_or(0);
+
+        Scene {
+            width,
+            height,
+            rects: rects.to_vec(),
+        }
+    }
+
+    pub fn add(&mut self, rect: Rectangle<u32>) {
+        self.rects.push(rect);
+    }
+
+    // Every character covers `scale` x `scale` units, 0 is treated as 1. Rectangles are drawn
+    // as outlines in the order they were added and marked with a letter (`A` for the first one,
+    // `B` for the second...) in their top left corner when they are large enough.
+    pub fn to_ascii(&self, scale: u32) -> String {
+        let scale = scale.max(1);
+        let columns = cells(self.width, scale);
+        let rows = cells(self.height, scale);
+        let mut grid = vec![vec!['.'; columns]; rows];
+
+        for (index, rect) in self.rects.iter().enumerate() {
+            if rect.width() == 0 || rect.height() == 0 {
+                continue;
+            }
+
+            let left = (rect.x() / scale) as usize;
+            let top = (rect.y() / scale) as usize;
+            let right = ((rect.right() - 1) / scale) as usize;
+            let bottom = ((rect.bottom() - 1) / scale) as usize;
+            if left >= columns || top >= rows {
+                continue;
+            }
+
+            // Only the visible part is drawn, edges outside the scene are cut off
+            let visible_right = right.min(columns - 1);
+            let visible_bottom = bottom.min(rows - 1);
+
+            for (row, line) in grid
+                .iter_mut()
+                .enumerate()
+                .take(visible_bottom + 1)
+                .skip(top)
+            {
+                for (column, cell) in line
+                    .iter_mut()
+                    .enumerate()
+                    .take(visible_right + 1)
+                    .skip(left)
+                {
+                    let horizontal_edge = row == top || row == bottom;
+                    let vertical_edge = column == left || column == right;
+
+                    *cell = match (horizontal_edge, vertical_edge) {
+                        (true, true) => '+',
+                        (true, false) => '-',
+                        (false, true) => '|',
+                        (false, false) => continue,
+                    };
+                }
+            }
+
+            if right > left + 1 && bottom > top + 1 && top + 1 < rows && left + 1 < columns {
+                grid[top + 1][left + 1] = label(index);
+            }
+        }
+
+        let mut out = String::new();
+        for line in grid {
+            out.extend(line);
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            w = self.width,
+            h = self.height
+        );
+        out.push_str(&format!(
+            "  <rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"white\" stroke=\"black\"/>\n",
+            self.width, self.height
+        ));
+
+        for (index, rect) in self.rects.iter().enumerate() {
+            out.push_str(&format!(
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.6\" stroke=\"black\"><title>{} {}x{}</title></rect>\n",
+                rect.x(),
+                rect.y(),
+                rect.width(),
+                rect.height(),
+                PALETTE[index % PALETTE.len()],
+                label(index),
+                rect.width(),
+                rect.height()
+            ));
+        }
+
+        out.push_str("</svg>\n");
+        out
+    }
+
+    pub fn write_svg<P: AsRef<Path>>(&self, path: P) -> Result<(), io::Error> {
+        fs::write(path, self.to_svg())
+    }
+}
+
+fn cells(size: u32, scale: u32) -> usize {
+    size.div_ceil(scale) as usize
+}
+
+// `A` to `Z`, then starting over
+fn label(index: usize) -> char {
+    (b'A' + (index % 26) as u8) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn rectangles_are_drawn_as_labelled_outlines() {
+        let mut scene = Scene::new(8, 4);
+        scene.add(Rectangle::new(0, 0, 4, 3));
+        scene.add(Rectangle::new(5, 1, 3, 3));
+        scene.add(Rectangle::new(7, 0, 1, 1));
+
+        assert_eq!(
+            scene.to_ascii(1),
+            "+--+...+\n|A.|.+-+\n+--+.|B|\n.....+-+\n"
+        );
+    }
+
+    #[test]
+    fn rectangles_outside_the_scene_are_cut_off() {
+        let mut scene = Scene::new(4, 4);
+        scene.add(Rectangle::new(10, 10, 5, 5));
+        assert_eq!(scene.to_ascii(1), "....\n....\n....\n....\n");
+
+        scene.add(Rectangle::new(2, 1, 5, 5));
+        scene.add(Rectangle::new(3, 3, 1, 1));
+        assert_eq!(scene.to_ascii(1), "....\n..+-\n..|B\n..|+\n");
+
+        let mut corner = Scene::new(3, 3);
+        corner.add(Rectangle::new(2, 2, 5, 5));
+        assert_eq!(corner.to_ascii(1), "...\n...\n..+\n");
+    }
+
+    #[test]
+    fn ascii_output_can_be_scaled_down() {
+        let scene = Scene::fit(&[Rectangle::new(0, 0, 40, 20), Rectangle::new(40, 0, 20, 20)]);
+
+        assert_eq!(
+            scene.to_ascii(5),
+            "+------++--+\n|A.....||B.|\n|......||..|\n+------++--+\n"
+        );
+    }
+
+    #[test]
+    fn svg_is_written_to_a_file() {
+        let scene = Scene::fit(&[Rectangle::new(10, 20, 30, 40)]);
+        let path = env::temp_dir().join(format!("scene_{}.svg", std::process::id()));
+
+        scene.write_svg(&path).unwrap();
+        let svg = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(
+            svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"60\"")
+        );
+        assert!(svg.contains("<rect x=\"10\" y=\"20\" width=\"30\" height=\"40\" fill=\"#4e79a7\""));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
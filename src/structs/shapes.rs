@@ -0,0 +1,422 @@
+// Shapes that layout code can handle without knowing what they are. Everything is measured in
+// `f64`, generic rectangles are converted when needed. Points on the right and bottom edge of a
+// rectangle are outside of it (like `Rectangle::contains_point`), the other shapes include
+// their boundary.
+
+use std::f64::consts::PI;
+
+use super::rectangle::{self, Rectangle};
+use crate::generics_traits_lifetimes::number::Number;
+
+pub trait Shape {
+    fn area(&self) -> f64;
+    fn perimeter(&self) -> f64;
+    // Smallest axis aligned rectangle containing the whole shape, `None` when its edges do not
+    // fit into `f64`
+    fn bounding_box(&self) -> Option<Rectangle<f64>>;
+    fn contains_point(&self, x: f64, y: f64) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    pub fn distance(&self, other: &Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+impl<T: Number> Shape for Rectangle<T> {
+    fn area(&self) -> f64 {
+        self.width().to_f64() * self.height().to_f64()
+    }
+
+    fn perimeter(&self) -> f64 {
+        2.0 * (self.width().to_f64() + self.height().to_f64())
+    }
+
+    fn bounding_box(&self) -> Option<Rectangle<f64>> {
+        Rectangle::checked_new(
+            self.x().to_f64(),
+            self.y().to_f64(),
+            self.width().to_f64(),
+            self.height().to_f64(),
+        )
+    }
+
+    fn contains_point(&self, x: f64, y: f64) -> bool {
+        x >= self.x().to_f64()
+            && x < self.right().to_f64()
+            && y >= self.y().to_f64()
+            && y < self.bottom().to_f64()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    center: Point,
+    radius: f64,
+}
+
+impl Circle {
+    // A negative radius is treated as zero
+    pub fn new(center: Point, radius: f64) -> Circle {
+        Circle {
+            center,
+            radius: radius.max(0.0),
+        }
+    }
+
+    pub fn center(&self) -> Point {
+        self.center
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+impl Shape for Circle {
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn perimeter(&self) -> f64 {
+        2.0 * PI * self.radius
+    }
+
+    fn bounding_box(&self) -> Option<Rectangle<f64>> {
+        Rectangle::checked_new(
+            self.center.x - self.radius,
+            self.center.y - self.radius,
+            2.0 * self.radius,
+            2.0 * self.radius,
+        )
+    }
+
+    fn contains_point(&self, x: f64, y: f64) -> bool {
+        self.center.distance(&Point::new(x, y)) <= self.radius
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle {
+    pub a: Point,
+    pub b: Point,
+    pub c: Point,
+}
+
+impl Triangle {
+    pub fn new(a: Point, b: Point, c: Point) -> Triangle {
+        Triangle { a, b, c }
+    }
+}
+
+impl Shape for Triangle {
+    fn area(&self) -> f64 {
+        cross(&self.a, &self.b, &self.c).abs() / 2.0
+    }
+
+    fn perimeter(&self) -> f64 {
+        self.a.distance(&self.b) + self.b.distance(&self.c) + self.c.distance(&self.a)
+    }
+
+    fn bounding_box(&self) -> Option<Rectangle<f64>> {
+        points_bounding_box(&[self.a, self.b, self.c])
+    }
+
+    // The point is inside when it is on the same side of all three edges. A flat triangle
+    // only contains the points on its edges.
+    fn contains_point(&self, x: f64, y: f64) -> bool {
+        let p = Point::new(x, y);
+        if collinear(&self.a, &self.b, &self.c) {
+            return on_segment(&self.a, &self.b, &p)
+                || on_segment(&self.b, &self.c, &p)
+                || on_segment(&self.c, &self.a, &p);
+        }
+
+        let d1 = cross(&self.a, &self.b, &p);
+        let d2 = cross(&self.b, &self.c, &p);
+        let d3 = cross(&self.c, &self.a, &p);
+
+        let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+        let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+        !(has_negative && has_positive)
+    }
+}
+
+// A simple (not self intersecting) polygon, vertices in order around the outline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    // Returns `None` for fewer than three vertices
+    pub fn new(vertices: Vec<Point>) -> Option<Polygon> {
+        if vertices.len() < 3 {
+            return None;
+        }
+
+        Some(Polygon { vertices })
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (&Point, &Point)> {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+    }
+}
+
+impl Shape for Polygon {
+    // Shoelace formula
+    fn area(&self) -> f64 {
+        let twice_area: f64 = self.edges().map(|(p, q)| p.x * q.y - q.x * p.y).sum();
+        twice_area.abs() / 2.0
+    }
+
+    fn perimeter(&self) -> f64 {
+        self.edges().map(|(p, q)| p.distance(q)).sum()
+    }
+
+    fn bounding_box(&self) -> Option<Rectangle<f64>> {
+        points_bounding_box(&self.vertices)
+    }
+
+    // Points on an edge are inside, everything else is decided by counting how many edges a
+    // ray from the point to the right crosses
+    fn contains_point(&self, x: f64, y: f64) -> bool {
+        let point = Point::new(x, y);
+        let mut inside = false;
+
+        for (p, q) in self.edges() {
+            if on_segment(p, q, &point) {
+                return true;
+            }
+
+            if (p.y > y) != (q.y > y) && x < p.x + (y - p.y) * (q.x - p.x) / (q.y - p.y) {
+                inside = !inside;
+            }
+        }
+
+        inside
+    }
+}
+
+// Twice the signed area of the triangle `a`, `b`, `c`; positive when counter clockwise
+fn cross(a: &Point, b: &Point, c: &Point) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+// Allowed distance from a line, relative to the lengths involved so rounding errors are
+// tolerated the same way for small and large coordinates
+const COLLINEAR_TOLERANCE: f64 = 1e-9;
+
+fn collinear(p: &Point, q: &Point, point: &Point) -> bool {
+    let scale = p.distance(q) * p.distance(point).max(q.distance(point));
+    cross(p, q, point).abs() <= COLLINEAR_TOLERANCE * scale
+}
+
+fn on_segment(p: &Point, q: &Point, point: &Point) -> bool {
+    collinear(p, q, point)
+        && point.x >= p.x.min(q.x)
+        && point.x <= p.x.max(q.x)
+        && point.y >= p.y.min(q.y)
+        && point.y <= p.y.max(q.y)
+}
+
+fn points_bounding_box(points: &[Point]) -> Option<Rectangle<f64>> {
+    let min_x = points.iter().map(|p| p.x).fold(f64::INFINITY, f64::min);
+    let min_y = points.iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+    let max_x = points.iter().map(|p| p.x).fold(f64::NEG_INFINITY, f64::max);
+    let max_y = points.iter().map(|p| p.y).fold(f64::NEG_INFINITY, f64::max);
+
+    Rectangle::checked_new(min_x, min_y, max_x - min_x, max_y - min_y)
+}
+
+pub fn total_area(shapes: &[Box<dyn Shape>]) -> f64 {
+    shapes.iter().map(|shape| shape.area()).sum()
+}
+
+// Bounding box around all shapes, `None` when there are none or the box does not fit
+pub fn bounding_box(shapes: &[Box<dyn Shape>]) -> Option<Rectangle<f64>> {
+    let boxes: Option<Vec<Rectangle<f64>>> =
+        shapes.iter().map(|shape| shape.bounding_box()).collect();
+    rectangle::bounding_box(&boxes?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn measurements_of_each_shape() {
+        let rect = Rectangle::new(0u32, 0, 4, 3);
+        assert!(close(Shape::area(&rect), 12.0));
+        assert!(close(Shape::perimeter(&rect), 14.0));
+
+        let circle = Circle::new(Point::new(1.0, 1.0), 2.0);
+        assert!(close(circle.area(), 4.0 * PI));
+        assert!(close(circle.perimeter(), 4.0 * PI));
+        assert_eq!(
+            circle.bounding_box(),
+            Some(Rectangle::new(-1.0, -1.0, 4.0, 4.0))
+        );
+
+        let triangle = Triangle::new(
+            Point::new(0.0, 0.0),
+            Point::new(4.0, 0.0),
+            Point::new(0.0, 3.0),
+        );
+        assert!(close(triangle.area(), 6.0));
+        assert!(close(triangle.perimeter(), 12.0));
+
+        // An L shape
+        let polygon = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 2.0),
+            Point::new(0.0, 2.0),
+        ])
+        .unwrap();
+        assert!(close(polygon.area(), 3.0));
+        assert!(close(polygon.perimeter(), 8.0));
+        assert_eq!(
+            polygon.bounding_box(),
+            Some(Rectangle::new(0.0, 0.0, 2.0, 2.0))
+        );
+    }
+
+    #[test]
+    fn contains_point_for_each_shape() {
+        let rect = Rectangle::new(0i32, 0, 4, 3);
+        assert!(Shape::contains_point(&rect, 0.0, 2.5));
+        assert!(!Shape::contains_point(&rect, 4.0, 1.0));
+
+        let circle = Circle::new(Point::new(0.0, 0.0), 1.0);
+        assert!(circle.contains_point(0.6, 0.6));
+        assert!(circle.contains_point(1.0, 0.0));
+        assert!(!circle.contains_point(0.8, 0.8));
+
+        let triangle = Triangle::new(
+            Point::new(0.0, 0.0),
+            Point::new(4.0, 0.0),
+            Point::new(0.0, 4.0),
+        );
+        assert!(triangle.contains_point(1.0, 1.0));
+        assert!(triangle.contains_point(2.0, 2.0));
+        assert!(!triangle.contains_point(3.0, 3.0));
+
+        let polygon = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 2.0),
+            Point::new(0.0, 2.0),
+        ])
+        .unwrap();
+        assert!(polygon.contains_point(0.5, 1.5));
+        assert!(polygon.contains_point(1.5, 1.0));
+        assert!(!polygon.contains_point(1.5, 1.5));
+    }
+
+    #[test]
+    fn flat_triangles_only_contain_their_edges() {
+        let flat = Triangle::new(
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 2.0),
+        );
+        assert!(flat.contains_point(0.5, 0.5));
+        assert!(flat.contains_point(2.0, 2.0));
+        assert!(!flat.contains_point(5.0, -3.0));
+        assert!(!flat.contains_point(3.0, 3.0));
+
+        let dot = Triangle::new(
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 1.0),
+        );
+        assert!(dot.contains_point(1.0, 1.0));
+        assert!(!dot.contains_point(1.0, 2.0));
+    }
+
+    #[test]
+    fn edges_are_found_for_large_coordinates() {
+        let a = Point::new(1e9, 1e9);
+        let b = Point::new(1e9 + 3e7, 1e9 + 7e7);
+        let c = Point::new(1e9, 1e9 + 9e7);
+        let middle = Point::new(a.x + (b.x - a.x) / 3.0, a.y + (b.y - a.y) / 3.0);
+
+        let polygon = Polygon::new(vec![a, b, c]).unwrap();
+        assert!(polygon.contains_point(middle.x, middle.y));
+        assert!(!polygon.contains_point(middle.x + 1.0, middle.y));
+
+        let flat = Triangle::new(a, b, Point::new(2.0 * b.x - a.x, 2.0 * b.y - a.y));
+        assert!(flat.contains_point(middle.x, middle.y));
+        assert!(!flat.contains_point(middle.x + 1.0, middle.y));
+    }
+
+    #[test]
+    fn huge_shapes_have_no_bounding_box() {
+        let circle = Circle::new(Point::new(0.0, 0.0), 1e308);
+        assert_eq!(circle.bounding_box(), None);
+        assert!(circle.area().is_infinite());
+        assert!(circle.contains_point(1e307, 0.0));
+
+        let triangle = Triangle::new(
+            Point::new(-1e308, 0.0),
+            Point::new(1e308, 0.0),
+            Point::new(0.0, 1.0),
+        );
+        assert_eq!(triangle.bounding_box(), None);
+
+        let shapes: Vec<Box<dyn Shape>> =
+            vec![Box::new(circle), Box::new(Rectangle::new(0u32, 0, 2, 2))];
+        assert_eq!(bounding_box(&shapes), None);
+    }
+
+    #[test]
+    fn polygons_need_three_vertices() {
+        assert!(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 1.0)]).is_none());
+    }
+
+    #[test]
+    fn mixed_shapes_can_be_handled_together() {
+        let shapes: Vec<Box<dyn Shape>> = vec![
+            Box::new(Rectangle::new(0u32, 0, 2, 2)),
+            Box::new(Rectangle::new(-1.5, 3.0, 1.0, 1.0)),
+            Box::new(Circle::new(Point::new(5.0, 5.0), 1.0)),
+            Box::new(Triangle::new(
+                Point::new(0.0, 0.0),
+                Point::new(2.0, 0.0),
+                Point::new(0.0, 2.0),
+            )),
+        ];
+
+        assert!(close(total_area(&shapes), 4.0 + 1.0 + PI + 2.0));
+        assert_eq!(
+            bounding_box(&shapes),
+            Some(Rectangle::new(-1.5, 0.0, 7.5, 6.0))
+        );
+        assert_eq!(bounding_box(&[]), None);
+    }
+}
@@ -0,0 +1,329 @@
+// Stores many users and keeps usernames and email addresses unique. Both are compared
+// case-insensitively, so `Ameer` and `ameer` count as the same username.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::user_struct::{FieldChange, User, UserError, UserPatch};
+
+pub type UserId = u64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistryError {
+    UsernameTaken(String),
+    EmailTaken(String),
+    NotFound(UserId),
+    User(UserError),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryError::UsernameTaken(username) => {
+                write!(f, "username {:?} is already taken", username)
+            }
+            RegistryError::EmailTaken(email) => {
+                write!(f, "email address {:?} is already registered", email)
+            }
+            RegistryError::NotFound(id) => write!(f, "no user with id {}", id),
+            RegistryError::User(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<UserError> for RegistryError {
+    fn from(e: UserError) -> RegistryError {
+        RegistryError::User(e)
+    }
+}
+
+#[derive(Default)]
+pub struct UserRegistry {
+    users: HashMap<UserId, User>,
+    by_username: HashMap<String, UserId>,
+    by_email: HashMap<String, UserId>,
+    next_id: UserId,
+}
+
+fn index_key(value: &str) -> String {
+    value.to_lowercase()
+}
+
+impl UserRegistry {
+    pub fn new() -> UserRegistry {
+        UserRegistry::default()
+    }
+
+    // Validates and stores a new user, returning its id
+    pub fn register(&mut self, username: String, email: String) -> Result<UserId, RegistryError> {
+        self.insert(User::new(username, email)?)
+    }
+
+    pub fn insert(&mut self, user: User) -> Result<UserId, RegistryError> {
+        self.check_username_free(user.username(), None)?;
+        self.check_email_free(user.email(), None)?;
+
+        self.next_id += 1;
+        let id = self.next_id;
+        self.by_username.insert(index_key(user.username()), id);
+        self.by_email.insert(index_key(user.email()), id);
+        self.users.insert(id, user);
+
+        Ok(id)
+    }
+
+    pub fn get(&self, id: UserId) -> Option<&User> {
+        self.users.get(&id)
+    }
+
+    pub fn find_by_username(&self, username: &str) -> Option<(UserId, &User)> {
+        let id = *self.by_username.get(&index_key(username))?;
+        Some((id, &self.users[&id]))
+    }
+
+    pub fn find_by_email(&self, email: &str) -> Option<(UserId, &User)> {
+        let id = *self.by_email.get(&index_key(email))?;
+        Some((id, &self.users[&id]))
+    }
+
+    pub fn update_username(&mut self, id: UserId, username: String) -> Result<(), RegistryError> {
+        self.check_username_free(&username, Some(id))?;
+
+        let user = self.users.get_mut(&id).ok_or(RegistryError::NotFound(id))?;
+        let old_key = index_key(user.username());
+        user.set_username(username)?;
+
+        self.by_username.remove(&old_key);
+        self.by_username.insert(index_key(user.username()), id);
+        Ok(())
+    }
+
+    pub fn update_email(&mut self, id: UserId, email: String) -> Result<(), RegistryError> {
+        self.check_email_free(&email, Some(id))?;
+
+        let user = self.users.get_mut(&id).ok_or(RegistryError::NotFound(id))?;
+        let old_key = index_key(user.email());
+        user.set_email(email)?;
+
+        self.by_email.remove(&old_key);
+        self.by_email.insert(index_key(user.email()), id);
+        Ok(())
+    }
+
+    // Patches the user while keeping usernames and email addresses unique, returns the audit
+    // of changed fields
+    pub fn apply_patch(
+        &mut self,
+        id: UserId,
+        patch: &UserPatch,
+    ) -> Result<Vec<FieldChange>, RegistryError> {
+        if let Some(username) = &patch.username {
+            self.check_username_free(username, Some(id))?;
+        }
+        if let Some(email) = &patch.email {
+            self.check_email_free(email, Some(id))?;
+        }
+
+        let user = self.users.get_mut(&id).ok_or(RegistryError::NotFound(id))?;
+        let old_username = index_key(user.username());
+        let old_email = index_key(user.email());
+        let changes = user.apply(patch)?;
+
+        self.by_username.remove(&old_username);
+        self.by_username.insert(index_key(user.username()), id);
+        self.by_email.remove(&old_email);
+        self.by_email.insert(index_key(user.email()), id);
+        Ok(changes)
+    }
+
+    // Returns the user's new sign in count
+    pub fn record_sign_in(&mut self, id: UserId) -> Result<u64, RegistryError> {
+        let user = self.users.get_mut(&id).ok_or(RegistryError::NotFound(id))?;
+        Ok(user.record_sign_in()?)
+    }
+
+    pub fn activate(&mut self, id: UserId) -> Result<(), RegistryError> {
+        let user = self.users.get_mut(&id).ok_or(RegistryError::NotFound(id))?;
+        Ok(user.activate()?)
+    }
+
+    pub fn deactivate(&mut self, id: UserId) -> Result<(), RegistryError> {
+        let user = self.users.get_mut(&id).ok_or(RegistryError::NotFound(id))?;
+        Ok(user.deactivate()?)
+    }
+
+    // Deletes the user and frees its username and email address
+    pub fn remove(&mut self, id: UserId) -> Result<User, RegistryError> {
+        let user = self.users.remove(&id).ok_or(RegistryError::NotFound(id))?;
+
+        self.by_username.remove(&index_key(user.username()));
+        self.by_email.remove(&index_key(user.email()));
+        Ok(user)
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    // `except` is the user being updated, who may keep their own username
+    fn check_username_free(
+        &self,
+        username: &str,
+        except: Option<UserId>,
+    ) -> Result<(), RegistryError> {
+        match self.by_username.get(&index_key(username)) {
+            Some(id) if Some(*id) != except => {
+                Err(RegistryError::UsernameTaken(String::from(username)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_email_free(&self, email: &str, except: Option<UserId>) -> Result<(), RegistryError> {
+        match self.by_email.get(&index_key(email)) {
+            Some(id) if Some(*id) != except => Err(RegistryError::EmailTaken(String::from(email))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> (UserRegistry, UserId) {
+        let mut registry = UserRegistry::new();
+        let id = registry
+            .register(String::from("ameer"), String::from("ameer@example.com"))
+            .unwrap();
+        (registry, id)
+    }
+
+    #[test]
+    fn register_and_look_up_users() {
+        let (registry, id) = registry();
+
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.get(id).unwrap().username(), "ameer");
+        assert_eq!(registry.find_by_username("AMEER").unwrap().0, id);
+        assert_eq!(registry.find_by_email("Ameer@Example.com").unwrap().0, id);
+        assert!(registry.find_by_username("hamza").is_none());
+    }
+
+    #[test]
+    fn usernames_and_emails_are_unique() {
+        let (mut registry, _) = registry();
+
+        assert_eq!(
+            registry.register(String::from("Ameer"), String::from("other@example.com")),
+            Err(RegistryError::UsernameTaken(String::from("Ameer")))
+        );
+        assert_eq!(
+            registry.register(String::from("hamza"), String::from("AMEER@example.com")),
+            Err(RegistryError::EmailTaken(String::from("AMEER@example.com")))
+        );
+        assert_eq!(
+            registry.register(String::from("hamza"), String::from("nope")),
+            Err(RegistryError::User(UserError::InvalidEmail(String::from(
+                "nope"
+            ))))
+        );
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn updates_keep_indexes_in_sync() {
+        let (mut registry, id) = registry();
+        let other = registry
+            .register(String::from("hamza"), String::from("hamza@example.com"))
+            .unwrap();
+
+        registry
+            .update_username(id, String::from("ameer_h"))
+            .unwrap();
+        registry
+            .update_email(id, String::from("ah@example.com"))
+            .unwrap();
+
+        assert!(registry.find_by_username("ameer").is_none());
+        assert!(registry.find_by_email("ameer@example.com").is_none());
+        assert_eq!(registry.find_by_username("ameer_h").unwrap().0, id);
+        assert_eq!(registry.find_by_email("ah@example.com").unwrap().0, id);
+
+        // A user may change the case of their own username but not take someone else's
+        registry
+            .update_username(id, String::from("Ameer_H"))
+            .unwrap();
+        assert_eq!(
+            registry.update_username(other, String::from("ameer_h")),
+            Err(RegistryError::UsernameTaken(String::from("ameer_h")))
+        );
+        assert_eq!(
+            registry.update_email(other, String::from("not valid")),
+            Err(RegistryError::User(UserError::InvalidEmail(String::from(
+                "not valid"
+            ))))
+        );
+        assert_eq!(registry.get(other).unwrap().email(), "hamza@example.com");
+    }
+
+    #[test]
+    fn patches_respect_unique_indexes() {
+        let (mut registry, id) = registry();
+        let other = registry
+            .register(String::from("hamza"), String::from("hamza@example.com"))
+            .unwrap();
+
+        let taken = UserPatch {
+            email: Some(String::from("Hamza@example.com")),
+            ..UserPatch::default()
+        };
+        assert_eq!(
+            registry.apply_patch(id, &taken),
+            Err(RegistryError::EmailTaken(String::from("Hamza@example.com")))
+        );
+
+        let rename = UserPatch {
+            username: Some(String::from("ameer_h")),
+            ..UserPatch::default()
+        };
+        let changes = registry.apply_patch(id, &rename).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "username");
+        assert_eq!(registry.find_by_username("ameer_h").unwrap().0, id);
+        assert!(registry.find_by_username("ameer").is_none());
+        assert_eq!(
+            registry.find_by_email("hamza@example.com").unwrap().0,
+            other
+        );
+    }
+
+    #[test]
+    fn activate_deactivate_and_remove() {
+        let (mut registry, id) = registry();
+
+        registry.activate(id).unwrap();
+        assert!(registry.get(id).unwrap().is_active());
+        registry.deactivate(id).unwrap();
+        assert_eq!(
+            registry.deactivate(id),
+            Err(RegistryError::User(UserError::AlreadyInactive))
+        );
+
+        let removed = registry.remove(id).unwrap();
+        assert_eq!(removed.username(), "ameer");
+        assert!(registry.is_empty());
+        assert_eq!(registry.remove(id), Err(RegistryError::NotFound(id)));
+        assert_eq!(registry.activate(id), Err(RegistryError::NotFound(id)));
+
+        // The username and email address can be used again
+        assert!(registry
+            .register(String::from("ameer"), String::from("ameer@example.com"))
+            .is_ok());
+    }
+}